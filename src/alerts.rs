@@ -0,0 +1,64 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+impl Direction {
+    pub fn parse(raw: &str) -> Option<Direction> {
+        match raw.to_lowercase().as_str() {
+            "above" => Some(Direction::Above),
+            "below" => Some(Direction::Below),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Above => "above",
+            Direction::Below => "below",
+        }
+    }
+
+    pub fn is_triggered(self, price: f64, threshold: f64) -> bool {
+        match self {
+            Direction::Above => price >= threshold,
+            Direction::Below => price <= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether an alert was set with `!alert`, which can stack, or `!watch`,
+/// which keeps one per coin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    Alert,
+    Watch,
+}
+
+impl AlertKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AlertKind::Alert => "alert",
+            AlertKind::Watch => "watch",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub id: i64,
+    pub user_id: u64,
+    pub symbol: String,
+    pub coin_id: String,
+    pub direction: Direction,
+    pub threshold: f64,
+}
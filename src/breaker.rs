@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::config::RetryPolicy;
+
+/// Where a breaker is in its closed → open → half-open cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Requests flow; counts consecutive failures.
+    Closed { failures: u32 },
+    /// Requests are refused until the cooldown ends.
+    Open { until: Instant },
+    /// One probe request is in flight. Another is let through if it hasn't
+    /// reported back within a cooldown, in case it was cancelled.
+    HalfOpen { probe_started: Instant },
+}
+
+/// Stops requests to an API after `threshold` consecutive failures, then
+/// lets a single probe through once `cooldown` has passed.
+#[derive(Debug)]
+struct CircuitBreaker {
+    state: State,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            state: State::Closed { failures: 0 },
+            threshold,
+            cooldown,
+        }
+    }
+
+    /// Whether a request may be sent at `now`. Moves an open breaker whose
+    /// cooldown is over to half-open, letting this request through as the probe.
+    fn allow(&mut self, now: Instant) -> bool {
+        match self.state {
+            State::Closed { .. } => true,
+            State::Open { until } if now < until => false,
+            State::HalfOpen { probe_started } if now < probe_started + self.cooldown => false,
+            State::Open { .. } | State::HalfOpen { .. } => {
+                self.state = State::HalfOpen { probe_started: now };
+                true
+            },
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.state = State::Closed { failures: 0 };
+    }
+
+    /// Counts a failure, opening the breaker once there have been `threshold`
+    /// in a row or the half-open probe failed.
+    fn record_failure(&mut self, now: Instant) {
+        self.state = match self.state {
+            State::Closed { failures } if failures + 1 < self.threshold => State::Closed { failures: failures + 1 },
+            _ => State::Open { until: now + self.cooldown },
+        };
+    }
+}
+
+/// A circuit breaker per API host, shared through `CircuitBreakerContainer`.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    breakers: Mutex<HashMap<String, CircuitBreaker>>,
+}
+
+impl CircuitBreakers {
+    /// Runs `f` on the breaker for `api`, creating it from `retry` on first use.
+    /// Breakers are off when the policy's threshold is zero.
+    fn with_breaker(&self, api: &str, retry: RetryPolicy, f: impl FnOnce(&mut CircuitBreaker)) {
+        if retry.breaker_threshold == 0 {
+            return;
+        }
+        let mut breakers = self.breakers.lock().expect("breakers poisoned");
+        let breaker = breakers
+            .entry(api.to_string())
+            .or_insert_with(|| CircuitBreaker::new(retry.breaker_threshold, retry.breaker_cooldown));
+        f(breaker);
+    }
+
+    /// Whether a request to `api` may be sent now.
+    pub fn allow_request(&self, api: &str, retry: RetryPolicy) -> bool {
+        let mut allowed = true;
+        self.with_breaker(api, retry, |breaker| allowed = breaker.allow(Instant::now()));
+        allowed
+    }
+
+    /// Feeds the outcome of a request to `api` into its breaker.
+    pub fn record_request(&self, api: &str, retry: RetryPolicy, failed: bool) {
+        self.with_breaker(api, retry, |breaker| {
+            if failed {
+                breaker.record_failure(Instant::now());
+            } else {
+                breaker.record_success();
+            }
+        });
+    }
+}
+
+/// When each API host that answered 429 will next be asked, shared through
+/// `ProviderCooldown`.
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    until: Mutex<HashMap<String, Instant>>,
+}
+
+impl Cooldowns {
+    /// Holds off requests to `api` for `duration` after it kept rate-limiting us.
+    pub fn start(&self, api: &str, duration: Duration) {
+        warn!(api = %api, cooldown_secs = duration.as_secs(), "Rate limited, pausing requests");
+        let mut until = self.until.lock().expect("cooldowns poisoned");
+        until.insert(api.to_string(), Instant::now() + duration);
+    }
+
+    /// How much longer `api` is cooling down after a rate limit, if at all.
+    pub fn remaining(&self, api: &str) -> Option<Duration> {
+        let mut until = self.until.lock().expect("cooldowns poisoned");
+        let remaining = until.get(api)?.checked_duration_since(Instant::now());
+        if remaining.is_none() {
+            until.remove(api);
+        }
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_secs(30);
+
+    fn policy(threshold: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::from_millis(1),
+            breaker_threshold: threshold,
+            breaker_cooldown: COOLDOWN,
+        }
+    }
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, COOLDOWN);
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        assert_eq!(breaker.state, State::Closed { failures: 2 });
+        assert!(breaker.allow(now));
+
+        breaker.record_failure(now);
+        assert_eq!(breaker.state, State::Open { until: now + COOLDOWN });
+        assert!(!breaker.allow(now + COOLDOWN / 2));
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, COOLDOWN);
+        breaker.record_failure(now);
+        breaker.record_failure(now);
+        breaker.record_success();
+        breaker.record_failure(now);
+        assert_eq!(breaker.state, State::Closed { failures: 1 });
+    }
+
+    #[test]
+    fn lets_one_probe_through_after_the_cooldown() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(1, COOLDOWN);
+        breaker.record_failure(now);
+
+        let later = now + COOLDOWN;
+        assert!(breaker.allow(later));
+        assert_eq!(breaker.state, State::HalfOpen { probe_started: later });
+        assert!(!breaker.allow(later));
+    }
+
+    #[test]
+    fn successful_probe_closes_the_breaker() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(1, COOLDOWN);
+        breaker.record_failure(now);
+        assert!(breaker.allow(now + COOLDOWN));
+
+        breaker.record_success();
+        assert_eq!(breaker.state, State::Closed { failures: 0 });
+        assert!(breaker.allow(now + COOLDOWN));
+    }
+
+    #[test]
+    fn failed_probe_reopens_the_breaker() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(3, COOLDOWN);
+        for _ in 0..3 {
+            breaker.record_failure(now);
+        }
+        let probe = now + COOLDOWN;
+        assert!(breaker.allow(probe));
+
+        breaker.record_failure(probe);
+        assert_eq!(breaker.state, State::Open { until: probe + COOLDOWN });
+        assert!(!breaker.allow(probe));
+    }
+
+    #[test]
+    fn lost_probe_is_replaced_after_a_cooldown() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreaker::new(1, COOLDOWN);
+        breaker.record_failure(now);
+        assert!(breaker.allow(now + COOLDOWN));
+
+        assert!(breaker.allow(now + COOLDOWN * 2));
+        assert_eq!(breaker.state, State::HalfOpen { probe_started: now + COOLDOWN * 2 });
+    }
+
+    #[test]
+    fn breakers_are_kept_per_api() {
+        let breakers = CircuitBreakers::default();
+        breakers.record_request("api.etherscan.io", policy(1), true);
+        assert!(!breakers.allow_request("api.etherscan.io", policy(1)));
+        assert!(breakers.allow_request("api.coingecko.com", policy(1)));
+    }
+
+    #[test]
+    fn cooldowns_are_kept_per_api_until_they_end() {
+        let cooldowns = Cooldowns::default();
+        cooldowns.start("api.coingecko.com", COOLDOWN);
+        assert!(cooldowns.remaining("api.coingecko.com").map_or(false, |remaining| remaining <= COOLDOWN));
+        assert_eq!(cooldowns.remaining("api.etherscan.io"), None);
+
+        cooldowns.start("api.coingecko.com", Duration::ZERO);
+        assert_eq!(cooldowns.remaining("api.coingecko.com"), None);
+        assert!(cooldowns.until.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn zero_threshold_turns_breakers_off() {
+        let breakers = CircuitBreakers::default();
+        for _ in 0..10 {
+            breakers.record_request("api.etherscan.io", policy(0), true);
+        }
+        assert!(breakers.allow_request("api.etherscan.io", policy(0)));
+    }
+}
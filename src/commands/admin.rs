@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use tracing::info;
+
+use super::meta::STATS_LIMIT;
+use super::{reply_chunked, sanitize, usage};
+use crate::state::{
+    flush_command_counter, storage, AllowedChannels, CommandFailureCounter, GuildPrefixes, GuildSettingsContainer,
+    ShardManagerContainer,
+};
+
+#[command]
+#[aliases("shutdown")]
+async fn quit(ctx: &Context, msg: &Message) -> CommandResult {
+    let shard_manager = {
+        let data = ctx.data.read().await;
+        data.get::<ShardManagerContainer>().cloned()
+    };
+
+    match shard_manager {
+        Some(manager) => {
+            msg.reply(ctx, "Shutting down...").await?;
+            flush_command_counter(&ctx.data).await;
+            manager.lock().await.shutdown_all().await;
+        },
+        None => {
+            msg.reply(ctx, "There was a problem getting the shard manager").await?;
+        }
+    }
+    Ok(())
+}
+
+#[command]
+async fn errors(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut failures: Vec<(String, u64, Option<String>)> = {
+        let data = ctx.data.read().await;
+        let counter = data.get::<CommandFailureCounter>().expect("Expected CommandFailureCounter in TypeMap.");
+        counter
+            .iter()
+            .map(|(name, failures)| (name.clone(), failures.count, failures.recent.back().cloned()))
+            .collect()
+    };
+    if failures.is_empty() {
+        msg.reply(&ctx.http, "No command has failed since startup.").await?;
+        return Ok(());
+    }
+    failures.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    failures.truncate(STATS_LIMIT);
+
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.embed(|e| {
+            e.title("Command failures");
+            for (name, count, last) in &failures {
+                // Embed field values are capped at 1024 characters.
+                let last: String = last.as_deref().unwrap_or("").chars().take(200).collect();
+                e.field(format!("{} ({})", name, count), format!("Last: `{}`", last), false);
+            }
+            e
+        })
+    }).await?;
+    Ok(())
+}
+
+const MAX_PREFIX_LEN: usize = 3;
+
+#[command]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+async fn setprefix(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let prefix = match args.single::<String>() {
+        Ok(prefix) => prefix,
+        Err(_) => {
+            msg.reply(&ctx.http, usage(ctx, msg, "setprefix <prefix>").await).await?;
+            return Ok(());
+        }
+    };
+    if prefix.chars().count() > MAX_PREFIX_LEN || prefix.chars().any(char::is_whitespace) {
+        msg.reply(&ctx.http, format!("The prefix must be 1-{} characters with no whitespace.", MAX_PREFIX_LEN)).await?;
+        return Ok(());
+    }
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    storage(ctx).await.set_guild_prefix(guild_id.0, &prefix)?;
+    {
+        let mut data = ctx.data.write().await;
+        let prefixes = data.get_mut::<GuildPrefixes>().expect("Expected GuildPrefixes in TypeMap.");
+        prefixes.insert(guild_id, prefix.clone());
+    }
+
+    msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Command prefix set to `{}`", prefix))).await?;
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+async fn embeds(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let use_embeds = match args.single::<String>().map(|raw| raw.to_lowercase()).as_deref() {
+        Ok("on") => true,
+        Ok("off") => false,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "embeds on|off").await).await?;
+            return Ok(());
+        }
+    };
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    storage(ctx).await.set_guild_embeds(guild_id.0, use_embeds)?;
+    {
+        let mut data = ctx.data.write().await;
+        let settings = data.get_mut::<GuildSettingsContainer>().expect("Expected GuildSettingsContainer in TypeMap.");
+        settings.entry(guild_id).or_default().use_embeds = use_embeds;
+    }
+
+    let reply = if use_embeds { "Price replies will use embeds." } else { "Price replies will be plain text." };
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+/// Adds or removes `channel_id` from the allow-list and persists the result,
+/// returning whether the list changed.
+async fn update_allowed_channels(ctx: &Context, channel_id: ChannelId, allow: bool) -> rusqlite::Result<bool> {
+    let allowed: HashSet<u64> = {
+        let mut data = ctx.data.write().await;
+        let allowed = data.get_mut::<AllowedChannels>().expect("Expected AllowedChannels in TypeMap.");
+        let changed = if allow { allowed.insert(channel_id) } else { allowed.remove(&channel_id) };
+        if !changed {
+            return Ok(false);
+        }
+        allowed.iter().map(|channel_id| channel_id.0).collect()
+    };
+    storage(ctx).await.set_allowed_channels(&allowed)?;
+    Ok(true)
+}
+
+#[command]
+#[only_in(guilds)]
+async fn allowchannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel_id = args.single::<ChannelId>().unwrap_or(msg.channel_id);
+    let reply = if update_allowed_channels(ctx, channel_id, true).await? {
+        format!("Commands are now allowed in <#{}>.", channel_id)
+    } else {
+        format!("<#{}> is already allowed.", channel_id)
+    };
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+async fn denychannel(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let channel_id = args.single::<ChannelId>().unwrap_or(msg.channel_id);
+    let reply = if update_allowed_channels(ctx, channel_id, false).await? {
+        format!("<#{}> was removed from the allowed channels.", channel_id)
+    } else {
+        format!("<#{}> is not in the allowed channels.", channel_id)
+    };
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+#[command]
+async fn channels(ctx: &Context, msg: &Message) -> CommandResult {
+    let mut allowed: Vec<ChannelId> = {
+        let data = ctx.data.read().await;
+        data.get::<AllowedChannels>().expect("Expected AllowedChannels in TypeMap.").iter().copied().collect()
+    };
+    if allowed.is_empty() {
+        msg.reply(&ctx.http, "Commands are allowed in every channel.").await?;
+        return Ok(());
+    }
+    allowed.sort();
+    let list = allowed.iter().map(|channel_id| format!("<#{}>", channel_id)).collect::<Vec<_>>().join("\n");
+    reply_chunked(ctx, msg, &format!("Commands are allowed in:\n{}", list)).await?;
+    Ok(())
+}
+
+#[command]
+async fn alerts_clear(ctx: &Context, msg: &Message) -> CommandResult {
+    let removed = storage(ctx).await.clear_alerts()?;
+    info!(removed, user_id = %msg.author.id, "Cleared all alerts");
+    msg.reply(&ctx.http, format!("Removed {} alert(s) and watch(es).", removed)).await?;
+    Ok(())
+}
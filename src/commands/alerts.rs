@@ -0,0 +1,275 @@
+use std::sync::Arc;
+
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use super::{check_arg_count, normalize_symbol, reply_chunked, sanitize, usage};
+use crate::alerts::{AlertKind, Direction};
+use crate::config::Config;
+use crate::format::format_price;
+use crate::providers::{fetch_coingecko_price, fetch_prices, resolve_symbol, Upstream};
+use crate::storage::Store;
+use crate::state::{http_client, storage, upstream, SymbolCacheContainer};
+
+/// Checks every stored alert against current prices, DMing and removing the
+/// ones whose condition has been met.
+pub async fn poll_alerts(
+    http: Arc<Http>,
+    store: Arc<dyn Store>,
+    client: Arc<reqwest::Client>,
+    upstream: Upstream,
+    config: Arc<Config>,
+) {
+    let mut interval = tokio::time::interval(config.alert_poll_interval);
+    loop {
+        interval.tick().await;
+
+        let pending = match store.alerts() {
+            Ok(pending) => pending,
+            Err(why) => {
+                error!(error = %why, "Couldn't load price alerts");
+                continue;
+            }
+        };
+        if pending.is_empty() {
+            continue;
+        }
+
+        let mut ids: Vec<String> = pending.iter().map(|alert| alert.coin_id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        let prices = match fetch_prices(&client, &ids, "usd", &upstream).await {
+            Ok(prices) => prices,
+            Err(why) => {
+                warn!(error = %why, "Couldn't fetch prices for alerts");
+                continue;
+            }
+        };
+
+        for alert in pending {
+            let price = match prices.get(&alert.coin_id) {
+                Some(price) => *price,
+                None => continue,
+            };
+            if !alert.direction.is_triggered(price, alert.threshold) {
+                continue;
+            }
+
+            let content = format!(
+                "Price alert: {} is now {}, {} your threshold of {}",
+                alert.symbol.to_uppercase(),
+                format_price(price, "usd"),
+                alert.direction,
+                format_price(alert.threshold, "usd")
+            );
+            let sent = match UserId(alert.user_id).create_dm_channel(&*http).await {
+                Ok(channel) => channel.say(&http, content).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            match sent {
+                Ok(()) => {
+                    if let Err(why) = store.delete_alert(alert.id) {
+                        error!(alert_id = alert.id, error = %why, "Couldn't remove fired alert");
+                    }
+                },
+                Err(why) => warn!(alert_id = alert.id, user_id = alert.user_id, error = %why, "Couldn't DM price alert"),
+            }
+        }
+    }
+}
+
+#[command]
+async fn alert(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "alert <symbol> above|below <price>").await;
+    if !check_arg_count(ctx, msg, &args, 3..=3, &usage).await? {
+        return Ok(());
+    }
+    let symbol = args.single::<String>().map(|symbol| normalize_symbol(&symbol));
+    let direction = args.single::<String>().ok().and_then(|direction| Direction::parse(&direction));
+    let threshold = args.single::<f64>();
+    let (symbol, direction, threshold) = match (symbol, direction, threshold) {
+        (Ok(symbol), Some(direction), Ok(threshold)) if !symbol.is_empty() && threshold.is_finite() && threshold > 0.0 => {
+            (symbol, direction, threshold)
+        },
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    let store = storage(ctx).await;
+    let id = store.add_alert(msg.author.id.0, &symbol, &coin_id, direction, threshold)?;
+    msg.reply(
+        &ctx.http,
+        format!(
+            "Alert #{} set: I'll DM you when {} goes {} {}",
+            id,
+            symbol.to_uppercase(),
+            direction,
+            format_price(threshold, "usd")
+        ),
+    ).await?;
+    Ok(())
+}
+
+#[command]
+async fn alerts(ctx: &Context, msg: &Message) -> CommandResult {
+    let store = storage(ctx).await;
+    let active = store.user_alerts(msg.author.id.0, AlertKind::Alert)?;
+    if active.is_empty() {
+        msg.reply(&ctx.http, "You have no active alerts.").await?;
+        return Ok(());
+    }
+
+    let lines = active
+        .iter()
+        .map(|alert| {
+            format!(
+                "#{} {} {} {}",
+                alert.id,
+                alert.symbol.to_uppercase(),
+                alert.direction,
+                format_price(alert.threshold, "usd")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    reply_chunked(ctx, msg, &format!("Your active alerts:\n```\n{}\n```", lines)).await?;
+    Ok(())
+}
+
+#[command]
+async fn unalert(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let id = match args.single::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            msg.reply(&ctx.http, usage(ctx, msg, "unalert <id>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let store = storage(ctx).await;
+    if store.remove_alert(msg.author.id.0, id)? {
+        msg.reply(&ctx.http, format!("Removed alert #{}", id)).await?;
+    } else {
+        msg.reply(&ctx.http, format!("You have no alert #{}", id)).await?;
+    }
+    Ok(())
+}
+
+#[command]
+#[bucket = "api"]
+async fn watch(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let (symbol, threshold) = match (args.single::<String>(), args.single::<f64>()) {
+        (Ok(symbol), Ok(threshold)) if threshold.is_finite() && threshold > 0.0 => (normalize_symbol(&symbol), threshold),
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "watch <token> <price>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    // The direction is whichever way the price has to move to reach the threshold.
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let current = match fetch_coingecko_price(&client, &coin_id, "usd", &upstream).await {
+        Ok(current) => current,
+        Err(why) => {
+            error!(coin_id = %coin_id, error = %why, "Failed to fetch price for watch");
+            msg.reply(&ctx.http, why.user_message(&format!("{} price", symbol.to_uppercase()))).await?;
+            return Ok(());
+        }
+    };
+    let direction = if threshold > current { Direction::Above } else { Direction::Below };
+
+    let store = storage(ctx).await;
+    store.upsert_watch(msg.author.id.0, &symbol, &coin_id, direction, threshold)?;
+    msg.reply(
+        &ctx.http,
+        format!(
+            "Watching {}: I'll DM you when it goes {} {} (currently {})",
+            symbol.to_uppercase(),
+            direction,
+            format_price(threshold, "usd"),
+            format_price(current, "usd")
+        ),
+    ).await?;
+    Ok(())
+}
+
+#[command]
+async fn unwatch(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "unwatch <token>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+
+    let store = storage(ctx).await;
+    let removed = match coin_id {
+        Some(coin_id) => store.remove_watch(msg.author.id.0, &coin_id)?,
+        None => false,
+    };
+    if removed {
+        msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Stopped watching {}", symbol))).await?;
+    } else {
+        msg.reply(&ctx.http, sanitize(ctx, msg, &format!("You have no watch for {}", symbol))).await?;
+    }
+    Ok(())
+}
+
+#[command]
+async fn watches(ctx: &Context, msg: &Message) -> CommandResult {
+    let store = storage(ctx).await;
+    let active = store.user_alerts(msg.author.id.0, AlertKind::Watch)?;
+    if active.is_empty() {
+        msg.reply(&ctx.http, "You aren't watching any tokens.").await?;
+        return Ok(());
+    }
+
+    let lines = active
+        .iter()
+        .map(|alert| format!("{} {} {}", alert.symbol.to_uppercase(), alert.direction, format_price(alert.threshold, "usd")))
+        .collect::<Vec<_>>()
+        .join("\n");
+    reply_chunked(ctx, msg, &format!("Your watches:\n```\n{}\n```", lines)).await?;
+    Ok(())
+}
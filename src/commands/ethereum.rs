@@ -0,0 +1,176 @@
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use tracing::{error, warn};
+
+use super::price::{cached_eth_price, guild_settings, send_price_reply, PriceReply};
+use super::usage;
+use crate::format::format_price;
+use crate::providers::{etherscan_result, fetch_eth_balance, fetch_with_retry, is_eth_address, PriceError, ETHERSCAN_API_URL};
+use crate::state::{config, http_client, upstream};
+
+#[command]
+#[aliases("eth_balance")]
+#[bucket = "api"]
+async fn whois(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let address = match args.single::<String>() {
+        Ok(address) => address,
+        Err(_) => {
+            msg.reply(&ctx.http, usage(ctx, msg, "whois <address>").await).await?;
+            return Ok(());
+        }
+    };
+    if !is_eth_address(&address) {
+        msg.reply(&ctx.http, "That isn't a valid address. Expected 0x followed by 40 hex characters.").await?;
+        return Ok(());
+    }
+
+    let config = config(ctx).await;
+    let etherscan_api_key = match &config.etherscan_api_key {
+        Some(etherscan_api_key) => etherscan_api_key,
+        None => {
+            msg.reply(&ctx.http, "Balance lookups need an Etherscan API key, which isn't configured.").await?;
+            return Ok(());
+        }
+    };
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let balance = match fetch_eth_balance(&client, etherscan_api_key, &address, &upstream).await {
+        Ok(balance) => balance,
+        Err(why) => {
+            error!(address = %address, error = %why, "Failed to fetch ETH balance");
+            msg.reply(&ctx.http, why.user_message("balance")).await?;
+            return Ok(());
+        }
+    };
+
+    // The balance is still worth showing if the price isn't available.
+    let reply = match cached_eth_price(ctx, "usd").await {
+        Ok((price, _)) => format!("`{}` holds {:.4} ETH (≈ {})", address, balance, format_price(balance * price, "usd")),
+        Err(why) => {
+            warn!(error = %why, "Failed to fetch ETH price for whois");
+            format!("`{}` holds {:.4} ETH", address, balance)
+        }
+    };
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GasPrices {
+    pub safe: u64,
+    pub propose: u64,
+    pub fast: u64,
+}
+
+/// Etherscan may report gas prices with a fractional part; round to whole gwei.
+fn parse_gwei(value: &Value) -> Option<u64> {
+    value.as_str()?.parse::<f64>().ok().map(|gwei| gwei.round() as u64)
+}
+
+/// Looks up current gas prices, or the message to show the user instead.
+pub async fn gas_prices(ctx: &Context) -> Result<GasPrices, String> {
+    let config = config(ctx).await;
+    let etherscan_api_key = match &config.etherscan_api_key {
+        Some(etherscan_api_key) => etherscan_api_key,
+        None => return Err("Gas prices need an Etherscan API key, which isn't configured.".to_string()),
+    };
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let url = format!("{}?module=gastracker&action=gasoracle&apikey={}", ETHERSCAN_API_URL, etherscan_api_key);
+    match fetch_with_retry(&client, &url, &upstream, parse_gas_prices).await {
+        Ok(prices) => Ok(prices),
+        Err(PriceError::Busy) => Err("Gas data is temporarily unavailable, try again in a few seconds.".to_string()),
+        Err(PriceError::Unavailable) => Err("Etherscan is temporarily unavailable, try again shortly.".to_string()),
+        Err(PriceError::RateLimited) => Err(PriceError::RateLimited.user_message("gas prices")),
+        Err(PriceError::Upstream(reason)) => Err(format!("Etherscan couldn't provide gas prices: {}", reason)),
+        Err(PriceError::MissingField(_)) => Err("Gas prices are unavailable right now, try again later.".to_string()),
+        Err(why) => {
+            warn!(error = %why, "Failed to fetch gas prices");
+            Err("Couldn't read gas prices from Etherscan, try again later.".to_string())
+        }
+    }
+}
+
+fn parse_gas_prices(body: &str) -> Result<GasPrices, PriceError> {
+    let result = etherscan_result(body)?;
+    let gwei = |field: &'static str| parse_gwei(&result[field]).ok_or(PriceError::MissingField(field));
+    Ok(GasPrices {
+        safe: gwei("SafeGasPrice")?,
+        propose: gwei("ProposeGasPrice")?,
+        fast: gwei("FastGasPrice")?,
+    })
+}
+
+pub fn gas_embed(embed: &mut CreateEmbed, prices: &GasPrices) -> &mut CreateEmbed {
+    embed.title("Ethereum Gas Prices").description(format!(
+        "**Safe:** {} gwei\n**Proposed:** {} gwei\n**Fast:** {} gwei",
+        prices.safe, prices.propose, prices.fast
+    ))
+}
+
+#[command]
+#[bucket = "api"]
+async fn gas(ctx: &Context, msg: &Message) -> CommandResult {
+    match gas_prices(ctx).await {
+        Ok(prices) => {
+            send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Gas(&prices)).await?;
+        },
+        Err(reason) => {
+            msg.reply(&ctx.http, reason).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gas_body(safe: &str, propose: &str, fast: &str) -> String {
+        format!(
+            r#"{{"status":"1","message":"OK","result":{{"SafeGasPrice":"{}","ProposeGasPrice":"{}","FastGasPrice":"{}"}}}}"#,
+            safe, propose, fast
+        )
+    }
+
+    #[test]
+    fn gwei_strings_are_rounded_to_whole_gwei() {
+        for (raw, gwei) in [("12", Some(12)), ("0", Some(0)), ("12.4", Some(12)), ("12.5", Some(13)), ("0.98", Some(1))] {
+            assert_eq!(parse_gwei(&Value::from(raw)), gwei, "{}", raw);
+        }
+        for raw in [Value::from(""), Value::from("twelve"), Value::from(12), Value::Null] {
+            assert_eq!(parse_gwei(&raw), None, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn gas_prices_are_parsed() {
+        let cases = [
+            (gas_body("10", "12", "15"), GasPrices { safe: 10, propose: 12, fast: 15 }),
+            (gas_body("9.6", "12.2", "15.5"), GasPrices { safe: 10, propose: 12, fast: 16 }),
+        ];
+        for (body, prices) in cases {
+            assert_eq!(parse_gas_prices(&body).unwrap(), prices, "{}", body);
+        }
+    }
+
+    #[test]
+    fn missing_gas_price_is_reported() {
+        let body = r#"{"status":"1","message":"OK","result":{"SafeGasPrice":"10","ProposeGasPrice":"12"}}"#;
+        assert!(matches!(parse_gas_prices(body), Err(PriceError::MissingField("FastGasPrice"))));
+        let body = gas_body("10", "not a number", "15");
+        assert!(matches!(parse_gas_prices(&body), Err(PriceError::MissingField("ProposeGasPrice"))));
+    }
+
+    #[test]
+    fn etherscan_errors_are_not_gas_prices() {
+        let body = r#"{"status":"0","message":"NOTOK","result":"Max rate limit reached"}"#;
+        assert!(matches!(parse_gas_prices(body), Err(PriceError::Busy)));
+        let body = r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#;
+        assert!(matches!(parse_gas_prices(body), Err(PriceError::Upstream(reason)) if reason == "Invalid API Key"));
+    }
+}
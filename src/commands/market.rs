@@ -0,0 +1,793 @@
+use std::borrow::Cow;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use plotters::prelude::{BitMapBackend, ChartBuilder, Color, IntoDrawingArea, LineSeries, GREEN, RED, WHITE};
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::{AttachmentType, Message};
+use serenity::model::Timestamp;
+use serenity::prelude::*;
+use serenity::utils::Colour;
+use tracing::{error, warn};
+
+use super::price::{guild_settings, send_price_reply, PriceReply};
+use super::{check_arg_count, normalize_symbol, sanitize, usage, Replied};
+use crate::format::{format_large_number, format_price, format_significant, format_thousands};
+use crate::providers::{
+    fetch_fear_greed, fetch_market_chart, fetch_markets, fetch_top_markets, fetch_trending, fetch_with_retry,
+    parse_global, resolve_symbol, MarketData,
+};
+use crate::state::{http_client, upstream, FearGreedCache, SymbolCacheContainer, TopMarketsCache, TrendingCache};
+
+const CHART_SIZE: (u32, u32) = (600, 200);
+
+/// Draws `prices` as an unlabelled line chart and encodes it as a PNG.
+fn render_sparkline(prices: &[f64]) -> Result<Vec<u8>, String> {
+    let (width, height) = CHART_SIZE;
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, CHART_SIZE).into_drawing_area();
+        root.fill(&WHITE).map_err(|why| why.to_string())?;
+
+        let min = prices.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        // A flat series still needs a non-empty range to draw into.
+        let padding = ((max - min) * 0.05).max(max.abs() * 0.001).max(f64::EPSILON);
+        let mut chart = ChartBuilder::on(&root)
+            .margin(10)
+            .build_cartesian_2d(0..prices.len(), (min - padding)..(max + padding))
+            .map_err(|why| why.to_string())?;
+        let color = if prices.last() >= prices.first() { GREEN } else { RED };
+        chart
+            .draw_series(LineSeries::new(prices.iter().cloned().enumerate(), color.stroke_width(2)))
+            .map_err(|why| why.to_string())?;
+        root.present().map_err(|why| why.to_string())?;
+    }
+
+    let image = image::RgbImage::from_raw(width, height, pixels).ok_or("chart buffer has the wrong size")?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .map_err(|why| why.to_string())?;
+    Ok(png)
+}
+
+#[command]
+#[bucket = "api"]
+async fn chart(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "chart <symbol> [1|7|30|90]").await;
+    if !check_arg_count(ctx, msg, &args, 1..=2, &usage).await? {
+        return Ok(());
+    }
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+    let days = if args.is_empty() { Ok(7) } else { args.single::<u32>() };
+    let days = match days {
+        Ok(days) if [1, 7, 30, 90].contains(&days) => days,
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let prices = match fetch_market_chart(&client, &coin_id, days, &upstream).await {
+        Ok(prices) => prices,
+        Err(why) => {
+            error!(coin_id = %coin_id, error = %why, "Failed to fetch market chart");
+            msg.reply(&ctx.http, why.user_message(&format!("{} history", symbol.to_uppercase()))).await?;
+            return Ok(());
+        }
+    };
+    if prices.is_empty() {
+        msg.reply(&ctx.http, format!("No price history for {} over the last {} days.", symbol.to_uppercase(), days)).await?;
+        return Ok(());
+    }
+
+    let png = match render_sparkline(&prices) {
+        Ok(png) => png,
+        Err(why) => {
+            msg.reply(&ctx.http, "Couldn't draw that chart, sorry.").await?;
+            return Err(Replied(format!("couldn't render chart for {}: {}", coin_id, why).into()).into());
+        }
+    };
+    let caption = format!(
+        "{} over the last {} day{}: {} → {}",
+        symbol.to_uppercase(),
+        days,
+        if days == 1 { "" } else { "s" },
+        format_price(prices[0], "usd"),
+        format_price(prices[prices.len() - 1], "usd")
+    );
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.content(caption).add_file(AttachmentType::Bytes {
+            data: Cow::Owned(png),
+            filename: "chart.png".to_string(),
+        })
+    }).await?;
+    Ok(())
+}
+
+const HISTORY_MAX_DAYS: i64 = 365;
+
+#[command]
+#[bucket = "api"]
+async fn history(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "history <token> [days]").await;
+    if !check_arg_count(ctx, msg, &args, 1..=2, &usage).await? {
+        return Ok(());
+    }
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+    let days = if args.is_empty() { Ok(7) } else { args.single::<i64>() };
+    let days = match days {
+        Ok(days) if (1..=HISTORY_MAX_DAYS).contains(&days) => days as u32,
+        Ok(_) => {
+            msg.reply(&ctx.http, format!("Days must be between 1 and {}.", HISTORY_MAX_DAYS)).await?;
+            return Ok(());
+        },
+        Err(_) => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let prices = match fetch_market_chart(&client, &coin_id, days, &upstream).await {
+        Ok(prices) => prices,
+        Err(why) => {
+            error!(coin_id = %coin_id, error = %why, "Failed to fetch market chart");
+            msg.reply(&ctx.http, why.user_message(&format!("{} history", symbol.to_uppercase()))).await?;
+            return Ok(());
+        }
+    };
+    let (first, last) = match (prices.first(), prices.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _ => {
+            msg.reply(&ctx.http, format!("No price history for {} over the last {} days.", symbol.to_uppercase(), days)).await?;
+            return Ok(());
+        }
+    };
+
+    let min = prices.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = prices.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let average = prices.iter().sum::<f64>() / prices.len() as f64;
+    let change = if first == 0.0 { 0.0 } else { (last - first) / first * 100.0 };
+
+    let reply = format!(
+        "{} over the last {} day{}:\n```\nMin:     ${}\nMax:     ${}\nAverage: ${}\nChange:  {:+.2}%\n```",
+        symbol.to_uppercase(),
+        days,
+        if days == 1 { "" } else { "s" },
+        format_thousands(min),
+        format_thousands(max),
+        format_thousands(average),
+        change
+    );
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+/// Looks up CoinGecko market data for `symbol`, replying to `msg` and
+/// returning `None` when the symbol is unknown or the fetch fails.
+async fn market_data(ctx: &Context, msg: &Message, symbol: &str) -> CommandResult<Option<MarketData>> {
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(None);
+        }
+    };
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    match fetch_markets(&client, &[coin_id.clone()], &upstream).await {
+        Ok(mut markets) if !markets.is_empty() => Ok(Some(markets.remove(0))),
+        Ok(_) => {
+            msg.reply(&ctx.http, format!("No market data for {}", symbol.to_uppercase())).await?;
+            Ok(None)
+        },
+        Err(why) => {
+            error!(coin_id = %coin_id, error = %why, "Failed to fetch market data");
+            msg.reply(&ctx.http, why.user_message(&format!("{} market data", symbol.to_uppercase()))).await?;
+            Ok(None)
+        }
+    }
+}
+
+#[command]
+#[bucket = "api"]
+async fn marketcap(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "marketcap <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let market = match market_data(ctx, msg, &symbol).await? {
+        Some(market) => market,
+        None => return Ok(()),
+    };
+
+    let mut lines = vec![format!("**{}** ({})", market.name, market.symbol.to_uppercase())];
+    if let Some(rank) = market.market_cap_rank {
+        lines.push(format!("Rank: #{}", rank));
+    }
+    lines.push(format!(
+        "Market cap: {}",
+        market.market_cap.map_or("unknown".to_string(), |cap| format!("${}", format_large_number(cap)))
+    ));
+    lines.push(format!(
+        "24h volume: {}",
+        market.total_volume.map_or("unknown".to_string(), |volume| format!("${}", format_large_number(volume)))
+    ));
+    msg.reply(&ctx.http, lines.join("\n")).await?;
+    Ok(())
+}
+
+const GAIN_COLOUR: Colour = Colour::from_rgb(0x2e, 0xcc, 0x71);
+const LOSS_COLOUR: Colour = Colour::from_rgb(0xe7, 0x4c, 0x3c);
+
+/// Green for a gain (or no data), red for a loss.
+pub fn change_colour(change: Option<f64>) -> Colour {
+    match change {
+        Some(change) if change < 0.0 => LOSS_COLOUR,
+        _ => GAIN_COLOUR,
+    }
+}
+
+/// Blends linearly from `from` at `t = 0.0` to `to` at `t = 1.0`.
+fn interpolate_colour(from: Colour, to: Colour, t: f64) -> Colour {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+    Colour::from_rgb(channel(from.r(), to.r()), channel(from.g(), to.g()), channel(from.b(), to.b()))
+}
+
+/// An embed field: its name, value and whether it sits inline.
+type Field = (String, String, bool);
+
+fn coin_title(market: &MarketData) -> String {
+    format!("{} ({})", market.name, market.symbol.to_uppercase())
+}
+
+fn day_change(market: &MarketData) -> Option<f64> {
+    market.price_change_percentage_24h.filter(|change| change.is_finite())
+}
+
+fn or_unknown(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "unknown".to_string())
+}
+
+fn usd_price(price: Option<f64>) -> String {
+    or_unknown(price.map(|price| format_price(price, "usd")))
+}
+
+fn usd_amount(amount: Option<f64>) -> String {
+    or_unknown(amount.map(|amount| format!("${}", format_large_number(amount))))
+}
+
+fn percent(change: Option<f64>) -> String {
+    or_unknown(change.map(|change| format!("{:+.2}%", change)))
+}
+
+fn market_fields(market: &MarketData) -> Vec<Field> {
+    [
+        ("Price", usd_price(market.current_price)),
+        ("24h change", percent(day_change(market))),
+        ("Market cap", usd_amount(market.market_cap)),
+        ("24h volume", usd_amount(market.total_volume)),
+        ("24h high", usd_price(market.high_24h)),
+        ("24h low", usd_price(market.low_24h)),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value, true))
+    .collect()
+}
+
+fn day_fields(market: &MarketData) -> Vec<Field> {
+    let mut fields = vec![
+        ("Price".to_string(), usd_price(market.current_price), false),
+        ("24h high".to_string(), usd_price(market.high_24h), true),
+        ("24h low".to_string(), usd_price(market.low_24h), true),
+    ];
+    if let Some(change) = day_change(market) {
+        fields.push(("24h change".to_string(), format!("{:+.2}%", change), false));
+    }
+    fields
+}
+
+/// One coin's market data as an embed, coloured by its 24h change.
+fn coin_embed<'a>(e: &'a mut CreateEmbed, market: &MarketData, fields: Vec<Field>) -> &'a mut CreateEmbed {
+    e.title(coin_title(market)).colour(change_colour(day_change(market)));
+    for (name, value, inline) in fields {
+        e.field(name, value, inline);
+    }
+    e
+}
+
+fn coin_text(market: &MarketData, fields: Vec<Field>) -> String {
+    let mut lines = vec![format!("**{}**", coin_title(market))];
+    lines.extend(fields.into_iter().map(|(name, value, _)| format!("{}: {}", name, value)));
+    lines.join("\n")
+}
+
+pub fn market_embed<'a>(e: &'a mut CreateEmbed, market: &MarketData) -> &'a mut CreateEmbed {
+    coin_embed(e, market, market_fields(market))
+}
+
+pub fn market_text(market: &MarketData) -> String {
+    coin_text(market, market_fields(market))
+}
+
+pub fn day_embed<'a>(e: &'a mut CreateEmbed, market: &MarketData) -> &'a mut CreateEmbed {
+    coin_embed(e, market, day_fields(market))
+}
+
+pub fn day_text(market: &MarketData) -> String {
+    coin_text(market, day_fields(market))
+}
+
+fn compare_title(a: &MarketData, b: &MarketData) -> String {
+    format!("{} vs {}", a.symbol.to_uppercase(), b.symbol.to_uppercase())
+}
+
+fn compare_column(market: &MarketData) -> String {
+    [
+        format!("Price: {}", usd_price(market.current_price)),
+        format!("Market cap: {}", usd_amount(market.market_cap)),
+        format!("24h change: {}", percent(day_change(market))),
+    ]
+    .join("\n")
+}
+
+/// How many of `b` one `a` buys, when both prices are known.
+fn compare_ratio(a: &MarketData, b: &MarketData) -> Option<String> {
+    match (a.current_price, b.current_price) {
+        (Some(price_a), Some(price_b)) if price_b > 0.0 => Some(format!(
+            "1 {} = {} {}",
+            a.symbol.to_uppercase(),
+            format_significant(price_a / price_b),
+            b.symbol.to_uppercase()
+        )),
+        _ => None,
+    }
+}
+
+pub fn compare_embed<'a>(e: &'a mut CreateEmbed, a: &MarketData, b: &MarketData) -> &'a mut CreateEmbed {
+    e.title(compare_title(a, b))
+        .field(coin_title(a), compare_column(a), true)
+        .field(coin_title(b), compare_column(b), true);
+    if let Some(ratio) = compare_ratio(a, b) {
+        e.description(ratio);
+    }
+    e
+}
+
+pub fn compare_text(a: &MarketData, b: &MarketData) -> String {
+    let mut lines = vec![format!("**{}**", compare_title(a, b))];
+    lines.extend(compare_ratio(a, b));
+    for market in [a, b] {
+        lines.push(format!("**{}**\n{}", coin_title(market), compare_column(market)));
+    }
+    lines.join("\n")
+}
+
+
+#[command]
+#[bucket = "api"]
+async fn price24h(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "price24h <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+    let market = match market_data(ctx, msg, &symbol).await? {
+        Some(market) => market,
+        None => return Ok(()),
+    };
+
+    send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Day(&market)).await?;
+    Ok(())
+}
+
+/// Shows two coins side by side, from one batched markets request.
+#[command]
+#[bucket = "api"]
+async fn compare(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "compare <a> <b>").await;
+    if !check_arg_count(ctx, msg, &args, 2..=2, &usage).await? {
+        return Ok(());
+    }
+    let (first, second) = match (args.single::<String>(), args.single::<String>()) {
+        (Ok(first), Ok(second)) => (normalize_symbol(&first), normalize_symbol(&second)),
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+
+    let resolved = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        [&first, &second].map(|symbol| (symbol, resolve_symbol(symbols, symbol)))
+    };
+    let missing: Vec<&str> = resolved
+        .iter()
+        .filter(|(_, coin_id)| coin_id.is_none())
+        .map(|(symbol, _)| symbol.as_str())
+        .collect();
+    if !missing.is_empty() {
+        let label = if missing.len() == 1 { "Unknown token" } else { "Unknown tokens" };
+        msg.reply(&ctx.http, sanitize(ctx, msg, &format!("{}: {}", label, missing.join(", ")))).await?;
+        return Ok(());
+    }
+    let ids: Vec<String> = resolved.into_iter().filter_map(|(_, coin_id)| coin_id).collect();
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let markets = match fetch_markets(&client, &ids, &upstream).await {
+        Ok(markets) => markets,
+        Err(why) => {
+            error!(error = %why, "Failed to fetch market data for compare");
+            msg.reply(&ctx.http, why.user_message("market data")).await?;
+            return Ok(());
+        }
+    };
+    let find = |coin_id: &str| markets.iter().find(|market| market.id == coin_id);
+    let (a, b) = match (find(&ids[0]), find(&ids[1])) {
+        (Some(a), Some(b)) => (a, b),
+        (None, _) => {
+            msg.reply(&ctx.http, format!("No market data for {}", first.to_uppercase())).await?;
+            return Ok(());
+        },
+        (_, None) => {
+            msg.reply(&ctx.http, format!("No market data for {}", second.to_uppercase())).await?;
+            return Ok(());
+        }
+    };
+
+    send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Compare(a, b)).await?;
+    Ok(())
+}
+
+/// Trending searches shift slowly, so the list is only refetched this often.
+const TRENDING_TTL: Duration = Duration::from_secs(5 * 60);
+
+const TRENDING_LIMIT: usize = 7;
+
+#[command]
+#[bucket = "api"]
+async fn trending(ctx: &Context, msg: &Message) -> CommandResult {
+    let cache = {
+        let data = ctx.data.read().await;
+        data.get::<TrendingCache>().cloned().expect("Expected TrendingCache in TypeMap.")
+    };
+    let cached = cache.lock().await.clone().filter(|(_, fetched_at)| fetched_at.elapsed() < TRENDING_TTL);
+    let coins = match cached {
+        Some((coins, _)) => coins,
+        None => {
+            let upstream = upstream(ctx).await;
+            let client = http_client(ctx).await;
+            match fetch_trending(&client, &upstream).await {
+                Ok(coins) => {
+                    *cache.lock().await = Some((coins.clone(), Instant::now()));
+                    coins
+                },
+                Err(why) => {
+                    error!(error = %why, "Failed to fetch trending coins");
+                    msg.reply(&ctx.http, why.user_message("trending coins")).await?;
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    if coins.is_empty() {
+        msg.reply(&ctx.http, "No trending coins right now.").await?;
+        return Ok(());
+    }
+    let lines = coins
+        .iter()
+        .take(TRENDING_LIMIT)
+        .enumerate()
+        .map(|(i, coin)| {
+            let rank = coin.market_cap_rank.map_or("unranked".to_string(), |rank| format!("rank #{}", rank));
+            format!("{}. **{}** ({}) - {}", i + 1, coin.name, coin.symbol.to_uppercase(), rank)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.embed(|e| e.title("Trending on CoinGecko").description(lines))
+    }).await?;
+    Ok(())
+}
+
+const TOP_DEFAULT: usize = 10;
+const TOP_MAX: usize = 25;
+/// The top list is fetched at full length and sliced, so any `n` within
+/// this window reuses one request.
+const TOP_TTL: Duration = Duration::from_secs(60);
+
+#[command]
+#[bucket = "api"]
+async fn top(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let count = match args.single::<usize>() {
+        Ok(count) if (1..=TOP_MAX).contains(&count) => count,
+        Err(_) if args.is_empty() => TOP_DEFAULT,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, &format!("top [1-{}]", TOP_MAX)).await).await?;
+            return Ok(());
+        }
+    };
+
+    let cache = {
+        let data = ctx.data.read().await;
+        data.get::<TopMarketsCache>().cloned().expect("Expected TopMarketsCache in TypeMap.")
+    };
+    let cached = cache.lock().await.clone().filter(|(_, fetched_at)| fetched_at.elapsed() < TOP_TTL);
+    let markets = match cached {
+        Some((markets, _)) => markets,
+        None => {
+            let upstream = upstream(ctx).await;
+            let client = http_client(ctx).await;
+            match fetch_top_markets(&client, TOP_MAX, &upstream).await {
+                Ok(markets) => {
+                    *cache.lock().await = Some((markets.clone(), Instant::now()));
+                    markets
+                },
+                Err(why) => {
+                    error!(error = %why, "Failed to fetch top coins");
+                    msg.reply(&ctx.http, why.user_message("the top coins")).await?;
+                    return Ok(());
+                }
+            }
+        }
+    };
+    if markets.is_empty() {
+        msg.reply(&ctx.http, "No market data right now.").await?;
+        return Ok(());
+    }
+
+    let lines = markets
+        .iter()
+        .take(count)
+        .enumerate()
+        .map(|(i, market)| {
+            let rank = market.market_cap_rank.unwrap_or(i as u32 + 1);
+            let price = market.current_price.map_or("-".to_string(), |price| format_price(price, "usd"));
+            let change = market
+                .price_change_percentage_24h
+                .filter(|change| change.is_finite())
+                .map_or(String::new(), |change| format!(" ({:+.2}%)", change));
+            format!("{}. **{}** ({}) {}{}", rank, market.name, market.symbol.to_uppercase(), price, change)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.embed(|e| e.title(format!("Top {} coins by market cap", count.min(markets.len()))).description(lines))
+    }).await?;
+    Ok(())
+}
+
+#[command]
+#[bucket = "api"]
+async fn supply(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "supply <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+    let market = match market_data(ctx, msg, &symbol).await? {
+        Some(market) => market,
+        None => return Ok(()),
+    };
+
+    if market.circulating_supply.is_none() && market.total_supply.is_none() && market.max_supply.is_none() {
+        msg.reply(&ctx.http, format!("Supply info is unavailable for {}.", market.symbol.to_uppercase())).await?;
+        return Ok(());
+    }
+    let unknown = || "unknown".to_string();
+    let lines = [
+        format!("**{}** ({}) supply", market.name, market.symbol.to_uppercase()),
+        format!("Circulating: {}", market.circulating_supply.map_or_else(unknown, format_large_number)),
+        format!("Total: {}", market.total_supply.map_or_else(unknown, format_large_number)),
+        format!("Max: {}", market.max_supply.map_or("∞ / uncapped".to_string(), format_large_number)),
+    ];
+    msg.reply(&ctx.http, lines.join("\n")).await?;
+    Ok(())
+}
+
+#[command]
+#[bucket = "api"]
+async fn dominance(ctx: &Context, msg: &Message) -> CommandResult {
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let global = fetch_with_retry(&client, "https://api.coingecko.com/api/v3/global", &upstream, parse_global).await;
+    let global = match global {
+        Ok(global) => global,
+        Err(why) => {
+            error!(error = %why, "Failed to fetch global market data");
+            msg.reply(&ctx.http, why.user_message("market dominance")).await?;
+            return Ok(());
+        }
+    };
+
+    let share = |symbol: &str| global.dominance.get(symbol).map_or("unknown".to_string(), |share| format!("{:.1}%", share));
+    let lines = [
+        format!("BTC dominance: {}", share("btc")),
+        format!("ETH dominance: {}", share("eth")),
+        format!(
+            "Total market cap: {}",
+            global.total_market_cap_usd.map_or("unknown".to_string(), |cap| format!("${}", format_large_number(cap)))
+        ),
+    ];
+    msg.reply(&ctx.http, lines.join("\n")).await?;
+    Ok(())
+}
+
+#[command]
+#[bucket = "api"]
+async fn market(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "market <token>").await).await?;
+            return Ok(());
+        }
+    };
+    let market = match market_data(ctx, msg, &symbol).await? {
+        Some(market) => market,
+        None => return Ok(()),
+    };
+
+    send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Market(&market)).await?;
+    Ok(())
+}
+
+/// The index is only published once a day.
+const FEAR_GREED_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[command]
+#[aliases("feargreed")]
+#[bucket = "api"]
+async fn fear(ctx: &Context, msg: &Message) -> CommandResult {
+    let cache = {
+        let data = ctx.data.read().await;
+        data.get::<FearGreedCache>().cloned().expect("Expected FearGreedCache in TypeMap.")
+    };
+    let cached = cache.lock().await.clone();
+    let index = match cached {
+        Some((index, fetched_at)) if fetched_at.elapsed() < FEAR_GREED_TTL => index,
+        stale => {
+            let upstream = upstream(ctx).await;
+            let client = http_client(ctx).await;
+            match fetch_fear_greed(&client, &upstream).await {
+                Ok(Some(index)) => {
+                    *cache.lock().await = Some((index.clone(), Instant::now()));
+                    index
+                },
+                Ok(None) => {
+                    msg.reply(&ctx.http, "The Fear & Greed Index is unavailable right now.").await?;
+                    return Ok(());
+                },
+                // An hours-old reading is still today's, so prefer it to no answer.
+                Err(why) => match stale {
+                    Some((index, _)) => {
+                        warn!(error = %why, "Failed to refresh the Fear & Greed Index, using the cached reading");
+                        index
+                    },
+                    None => {
+                        error!(error = %why, "Failed to fetch the Fear & Greed Index");
+                        msg.reply(&ctx.http, why.user_message("the Fear & Greed Index")).await?;
+                        return Ok(());
+                    }
+                },
+            }
+        }
+    };
+
+    let colour = interpolate_colour(LOSS_COLOUR, GAIN_COLOUR, index.value as f64 / 100.0);
+    let published = Timestamp::from_unix_timestamp(index.timestamp).ok();
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.embed(|e| {
+            e.title("Crypto Fear & Greed Index")
+                .colour(colour)
+                .field("Index", format!("{}/100", index.value), true)
+                .field("Classification", &index.classification, true)
+                .footer(|f| f.text("Source: alternative.me"));
+            if let Some(published) = published {
+                e.timestamp(published);
+            }
+            e
+        })
+    }).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(symbol: &str, price: Option<f64>) -> MarketData {
+        MarketData {
+            id: symbol.to_string(),
+            symbol: symbol.to_string(),
+            name: symbol.to_uppercase(),
+            current_price: price,
+            market_cap: None,
+            market_cap_rank: None,
+            total_volume: None,
+            high_24h: None,
+            low_24h: None,
+            price_change_percentage_24h: None,
+            circulating_supply: None,
+            total_supply: None,
+            max_supply: None,
+        }
+    }
+
+    #[test]
+    fn missing_market_data_reads_as_unknown() {
+        let text = day_text(&coin("eth", None));
+        assert_eq!(text, "**ETH (ETH)**\nPrice: unknown\n24h high: unknown\n24h low: unknown");
+        assert!(market_text(&coin("eth", None)).contains("24h change: unknown"));
+    }
+
+    #[test]
+    fn compare_ratio_needs_both_prices() {
+        assert_eq!(compare_ratio(&coin("eth", Some(3000.0)), &coin("btc", None)), None);
+        assert_eq!(compare_ratio(&coin("eth", Some(3000.0)), &coin("btc", Some(0.0))), None);
+        assert!(compare_ratio(&coin("eth", Some(3000.0)), &coin("btc", Some(60000.0))).is_some());
+    }
+}
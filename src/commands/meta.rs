@@ -0,0 +1,174 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use serenity::client::bridge::gateway::ShardId;
+use serenity::framework::standard::macros::{command, help};
+use serenity::framework::standard::{help_commands, Args, CommandGroup, CommandResult, HelpOptions};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::*;
+
+use super::command_prefix;
+use crate::format::format_uptime;
+use crate::state::{CommandCounter, ShardManagerContainer, StartTime};
+
+const HELP_TIP: &str = "Hello! Use `{prefix}` as a prefix for commands\n\n\
+If you want more information about a specific command, just pass the command as argument, \
+or pass `--compact` for a short list of every command.";
+
+/// `HELP_TIP` for `prefix`. Help options only hold `&'static str`, so each
+/// prefix's tip is built once and kept; `setprefix` allows few enough.
+fn help_tip(prefix: &str) -> &'static str {
+    static TIPS: OnceLock<Mutex<HashMap<String, &'static str>>> = OnceLock::new();
+    let mut tips = TIPS.get_or_init(Default::default).lock().expect("help tips poisoned");
+    *tips
+        .entry(prefix.to_string())
+        .or_insert_with(|| Box::leak(HELP_TIP.replace("{prefix}", prefix).into_boxed_str()))
+}
+
+#[help]
+#[command_not_found_text = "Could not find: `{}`."]
+#[max_levenshtein_distance(3)]
+#[indention_prefix = "+"]
+#[lacking_permissions = "Hide"]
+#[lacking_role = "Nothing"]
+// Commands whose checks (Owner, AllowedChannel) fail for the caller are hidden too.
+#[lacking_conditions = "Hide"]
+async fn my_help(
+    context: &Context,
+    msg: &Message,
+    args: Args,
+    help_options: &'static HelpOptions,
+    groups: &[&'static CommandGroup],
+    owners: HashSet<UserId>,
+) -> CommandResult {
+    if args.current() == Some("--compact") {
+        msg.channel_id.say(&context.http, compact_help(groups, owners.contains(&msg.author.id))).await?;
+        return Ok(());
+    }
+    let mut help_options = help_options.clone();
+    help_options.individual_command_tip = help_tip(&command_prefix(context, msg).await);
+    // Returning the error lets the `after` hook log and count it.
+    help_commands::with_embeds(context, msg, args, &help_options, groups, owners).await?;
+    Ok(())
+}
+
+/// One line per group naming its commands, for `help --compact`. Groups
+/// behind the `Owner` check are only listed for owners.
+fn compact_help(groups: &[&'static CommandGroup], is_owner: bool) -> String {
+    groups
+        .iter()
+        .filter(|group| is_owner || !group.options.checks.iter().any(|check| check.name == "Owner"))
+        .map(|group| {
+            let names = group
+                .options
+                .commands
+                .iter()
+                .filter_map(|command| command.options.names.first().copied())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("**{}:** {}", group.name, names)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Most commands `stats` lists, keeping the embed well under Discord's limits.
+pub const STATS_LIMIT: usize = 15;
+
+#[command]
+async fn stats(ctx: &Context, msg: &Message) -> CommandResult {
+    // Copy the counts out so the read guard is released before replying and
+    // the `before` hook can take its write lock.
+    let mut counts: Vec<(String, u64)> = {
+        let data = ctx.data.read().await;
+        let counter = data.get::<CommandCounter>().expect("Expected CommandCounter in TypeMap.");
+        counter.iter().map(|(name, count)| (name.clone(), *count)).collect()
+    };
+    if counts.is_empty() {
+        msg.reply(&ctx.http, "No commands have been run yet.").await?;
+        return Ok(());
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(STATS_LIMIT);
+
+    let width = counts.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let lines = counts
+        .iter()
+        .map(|(name, count)| format!("{:<width$}  {}", name, count, width = width))
+        .collect::<Vec<_>>()
+        .join("\n");
+    msg.channel_id.send_message(&ctx.http, |m| {
+        m.embed(|e| {
+            e.title("Command usage")
+                .description(format!("```\n{}\n```", lines))
+                .footer(|f| f.text(format!("Top {} commands", STATS_LIMIT)))
+        })
+    }).await?;
+    Ok(())
+}
+
+#[command]
+#[aliases("ping")]
+async fn latency(ctx: &Context, msg: &Message) -> CommandResult {
+    let shard_manager = {
+        let data = ctx.data.read().await;
+        data.get::<ShardManagerContainer>().cloned()
+    };
+    let shard_manager = match shard_manager {
+        Some(shard_manager) => shard_manager,
+        None => {
+            msg.reply(ctx, "There was a problem getting the shard manager").await?;
+            return Ok(());
+        }
+    };
+
+    let latency = {
+        let manager = shard_manager.lock().await;
+        let runners = manager.runners.lock().await;
+        runners.get(&ShardId(ctx.shard_id)).map(|runner| runner.latency)
+    };
+    match latency {
+        Some(Some(latency)) => {
+            msg.reply(ctx, format!("Pong! Gateway heartbeat round-trip: {}ms", latency.as_millis())).await?;
+        },
+        Some(None) => {
+            msg.reply(ctx, "Latency not yet measured").await?;
+        },
+        None => {
+            msg.reply(ctx, "No shard found").await?;
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[aliases("source")]
+async fn about(ctx: &Context, msg: &Message) -> CommandResult {
+    let uptime = {
+        let data = ctx.data.read().await;
+        data.get::<StartTime>().expect("Expected StartTime in TypeMap.").elapsed()
+    };
+    let lines = [
+        format!("**rusty-crypto** v{}", env!("CARGO_PKG_VERSION")),
+        format!("Commit: {}", option_env!("GIT_COMMIT").unwrap_or("unknown")),
+        format!("Uptime: {}", format_uptime(uptime)),
+        format!("Servers: {}", ctx.cache.guild_count()),
+        format!("Source: <{}>", env!("CARGO_PKG_REPOSITORY")),
+    ];
+    msg.reply(&ctx.http, lines.join("\n")).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_tip_names_the_prefix_and_is_built_once() {
+        let tip = help_tip("$$");
+        assert!(tip.starts_with("Hello! Use `$$` as a prefix"), "{}", tip);
+        assert!(std::ptr::eq(tip, help_tip("$$")));
+        assert!(help_tip("!").starts_with("Hello! Use `!` as a prefix"));
+    }
+}
@@ -0,0 +1,275 @@
+pub mod admin;
+pub mod alerts;
+pub mod ethereum;
+pub mod market;
+pub mod meta;
+pub mod portfolio;
+pub mod price;
+pub mod ticker;
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use serenity::cache::Cache;
+use serenity::framework::standard::macros::group;
+use serenity::framework::standard::{Args, CommandGroup, CommandOptions};
+use serenity::model::channel::Message;
+use serenity::model::id::GuildId;
+use serenity::model::user::User;
+use serenity::prelude::*;
+use serenity::utils::{content_safe, ContentSafeOptions};
+
+use crate::format::{split_message, MESSAGE_LIMIT};
+use crate::i18n::Locale;
+use crate::hooks::{ALLOWEDCHANNEL_CHECK, OWNER_CHECK};
+use crate::state::{ConfigContainer, GuildPrefixes};
+use self::admin::{
+    ALERTS_CLEAR_COMMAND, ALLOWCHANNEL_COMMAND, CHANNELS_COMMAND, DENYCHANNEL_COMMAND, EMBEDS_COMMAND, ERRORS_COMMAND,
+    QUIT_COMMAND, SETPREFIX_COMMAND,
+};
+use self::alerts::{ALERTS_COMMAND, ALERT_COMMAND, UNALERT_COMMAND, UNWATCH_COMMAND, WATCHES_COMMAND, WATCH_COMMAND};
+use self::ethereum::{GAS_COMMAND, WHOIS_COMMAND};
+use self::market::{
+    CHART_COMMAND, COMPARE_COMMAND, DOMINANCE_COMMAND, FEAR_COMMAND, HISTORY_COMMAND, MARKETCAP_COMMAND, MARKET_COMMAND,
+    PRICE24H_COMMAND, SUPPLY_COMMAND, TOP_COMMAND, TRENDING_COMMAND,
+};
+use self::meta::{ABOUT_COMMAND, LATENCY_COMMAND, STATS_COMMAND};
+use self::portfolio::{ADD_COMMAND, PORTFOLIO_COMMAND, REMOVE_COMMAND};
+use self::price::{CONVERT_COMMAND, ETH_PRICE_COMMAND, MYCURRENCY_COMMAND, SETCURRENCY_COMMAND, TOKEN_COMMAND};
+use self::ticker::{TICKER_COMMAND, UNTICKER_COMMAND};
+
+#[group]
+#[description = "Token, ETH and gas prices"]
+#[checks(AllowedChannel)]
+#[commands(eth_price, token, convert, setcurrency, mycurrency, gas, whois, ticker, unticker, chart, history)]
+struct Prices;
+
+#[group]
+#[description = "Market-wide figures from CoinGecko and alternative.me"]
+#[checks(AllowedChannel)]
+#[commands(marketcap, price24h, market, compare, supply, top, trending, dominance, fear)]
+struct Market;
+
+#[group]
+#[description = "Price alerts delivered by DM"]
+#[checks(AllowedChannel)]
+#[commands(alert, alerts, unalert, watch, unwatch, watches)]
+struct Alerts;
+
+#[group]
+#[description = "Track your holdings"]
+#[checks(AllowedChannel)]
+#[commands(add, remove, portfolio)]
+struct Portfolio;
+
+#[group]
+#[description = "About the bot"]
+#[checks(AllowedChannel)]
+#[commands(stats, latency, about)]
+struct General;
+
+#[group]
+#[description = "Bot owner only"]
+#[checks(Owner)]
+#[commands(quit, setprefix, embeds, allowchannel, denychannel, channels, alerts_clear, errors)]
+struct Admin;
+
+/// Every command group, in the order `help` lists them.
+pub static GROUPS: &[&CommandGroup] = &[
+    &PRICES_GROUP,
+    &MARKET_GROUP,
+    &ALERTS_GROUP,
+    &PORTFOLIO_GROUP,
+    &GENERAL_GROUP,
+    &ADMIN_GROUP,
+];
+
+/// Options of every command in `groups`, including sub-groups.
+fn group_commands(groups: &[&'static CommandGroup]) -> Vec<&'static CommandOptions> {
+    let mut commands = Vec::new();
+    for group in groups {
+        commands.extend(group.options.commands.iter().map(|command| command.options));
+        commands.extend(group_commands(group.options.sub_groups));
+    }
+    commands
+}
+
+/// Primary names of every command in `groups`, including sub-groups.
+pub fn command_names(groups: &[&'static CommandGroup]) -> Vec<&'static str> {
+    group_commands(groups).iter().filter_map(|options| options.names.first().copied()).collect()
+}
+
+/// Whether the command named `name` waits on an upstream API, going by its
+/// bucket, so it's worth a typing indicator while it runs.
+pub fn is_slow_command(name: &str) -> bool {
+    group_commands(GROUPS)
+        .into_iter()
+        .flat_map(|options| std::iter::once(options).chain(options.sub_commands.iter().map(|command| command.options)))
+        .any(|options| options.names.contains(&name) && matches!(options.bucket, Some("api" | "price")))
+}
+
+/// Every name and alias of the commands not listed in `enabled`.
+pub fn disabled_commands(groups: &[&'static CommandGroup], enabled: &HashSet<String>) -> HashSet<String> {
+    group_commands(groups)
+        .into_iter()
+        .filter(|options| !options.names.iter().any(|name| enabled.contains(*name)))
+        .flat_map(|options| options.names.iter().map(|name| name.to_string()))
+        .collect()
+}
+
+/// Canonical form of a user-typed ticker symbol. Callers reject the empty result.
+pub fn normalize_symbol(raw: &str) -> String {
+    raw.trim().to_lowercase()
+}
+
+/// The prefix commands take where `msg` was sent: the guild's own if it set
+/// one with `setprefix`, otherwise `COMMAND_PREFIX`.
+pub async fn command_prefix(ctx: &Context, msg: &Message) -> String {
+    let data = ctx.data.read().await;
+    let custom = msg.guild_id.and_then(|guild_id| {
+        data.get::<GuildPrefixes>().and_then(|prefixes| prefixes.get(&guild_id).cloned())
+    });
+    let config = data.get::<ConfigContainer>().expect("Expected ConfigContainer in TypeMap.");
+    custom.unwrap_or_else(|| config.command_prefix.clone())
+}
+
+/// `Usage: ` and `command` (the command name and its arguments) behind the
+/// prefix `msg` was sent with.
+pub async fn usage(ctx: &Context, msg: &Message, command: &str) -> String {
+    format!("Usage: {}{}", command_prefix(ctx, msg).await, command)
+}
+
+/// Whether `args` holds a number of arguments in `expected`. If not, replies
+/// with `usage` so the caller can just return.
+pub async fn check_arg_count(
+    ctx: &Context,
+    msg: &Message,
+    args: &Args,
+    expected: RangeInclusive<usize>,
+    usage: &str,
+) -> serenity::Result<bool> {
+    match arg_count_message(args.len(), &expected, usage) {
+        Some(message) => {
+            msg.reply(&ctx.http, message).await?;
+            Ok(false)
+        },
+        None => Ok(true),
+    }
+}
+
+/// What to tell the user when `given` arguments aren't in `expected`, or `None` if they are.
+pub fn arg_count_message(given: usize, expected: &RangeInclusive<usize>, usage: &str) -> Option<String> {
+    if expected.contains(&given) {
+        return None;
+    }
+    let wanted = if expected.start() == expected.end() {
+        expected.start().to_string()
+    } else {
+        format!("{}-{}", expected.start(), expected.end())
+    };
+    Some(format!("Expected {} argument(s) but got {}. {}", wanted, given, usage))
+}
+
+/// The language to reply to `msg` in: its guild's preferred locale, or English in DMs.
+pub fn message_locale(ctx: &Context, msg: &Message) -> Locale {
+    msg.guild_id
+        .and_then(|guild_id| ctx.cache.guild_field(guild_id, |guild| Locale::from_tag(&guild.preferred_locale)))
+        .unwrap_or_default()
+}
+
+/// Neutralises mentions (including `@everyone`) in replies that echo user input.
+pub fn sanitize(ctx: &Context, msg: &Message, content: &str) -> String {
+    clean_mentions(&ctx.cache, msg.guild_id, &msg.mentions, content)
+}
+
+/// What `sanitize` does, given the parts of the context and message it needs.
+fn clean_mentions(cache: &Arc<Cache>, guild_id: Option<GuildId>, mentions: &[User], content: &str) -> String {
+    let settings = if let Some(guild_id) = guild_id {
+        ContentSafeOptions::default()
+            .clean_channel(false)
+            .display_as_member_from(guild_id)
+    } else {
+        ContentSafeOptions::default().clean_channel(false).clean_role(false)
+    };
+    content_safe(cache, content, &settings, mentions)
+}
+
+/// A command error the user has already been told about. `after` logs and
+/// counts it like any other failure but skips the generic error reply.
+#[derive(Debug)]
+pub struct Replied(pub Box<dyn Error + Send + Sync>);
+
+impl fmt::Display for Replied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for Replied {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Replies with `content`, continuing in follow-up messages when it is over
+/// Discord's length limit.
+pub async fn reply_chunked(ctx: &Context, msg: &Message, content: &str) -> serenity::Result<()> {
+    let mut chunks = split_message(content, MESSAGE_LIMIT).into_iter();
+    if let Some(first) = chunks.next() {
+        msg.reply(&ctx.http, first).await?;
+    }
+    for chunk in chunks {
+        msg.channel_id.say(&ctx.http, chunk).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_symbol_trims_and_lowercases() {
+        assert_eq!(normalize_symbol("  ETH "), "eth");
+        assert_eq!(normalize_symbol("\tBtC\n"), "btc");
+        assert_eq!(normalize_symbol("uSdC"), "usdc");
+    }
+
+    #[test]
+    fn normalize_symbol_leaves_blank_input_empty() {
+        assert_eq!(normalize_symbol(""), "");
+        assert_eq!(normalize_symbol("   "), "");
+    }
+
+    #[test]
+    fn arg_count_message_is_none_within_the_range() {
+        assert_eq!(arg_count_message(1, &(1..=2), "Usage: !chart <symbol> [days]"), None);
+        assert_eq!(arg_count_message(2, &(1..=2), "Usage: !chart <symbol> [days]"), None);
+    }
+
+    #[test]
+    fn arg_count_message_names_the_expected_count() {
+        assert_eq!(
+            arg_count_message(2, &(3..=3), "Usage: !convert <amount> <from> <to>").as_deref(),
+            Some("Expected 3 argument(s) but got 2. Usage: !convert <amount> <from> <to>")
+        );
+        assert_eq!(
+            arg_count_message(0, &(1..=2), "Usage: !chart <symbol> [days]").as_deref(),
+            Some("Expected 1-2 argument(s) but got 0. Usage: !chart <symbol> [days]")
+        );
+    }
+
+    #[test]
+    fn sanitize_neutralises_everyone_and_here() {
+        let cache = Arc::new(Cache::new());
+        for guild_id in [None, Some(GuildId(1))] {
+            let cleaned = clean_mentions(&cache, guild_id, &[], "Unknown token: @everyone @here");
+            assert!(!cleaned.contains("@everyone"), "{:?}", cleaned);
+            assert!(!cleaned.contains("@here"), "{:?}", cleaned);
+            assert!(cleaned.starts_with("Unknown token: @"));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use tracing::error;
+
+use super::{check_arg_count, command_prefix, normalize_symbol, reply_chunked, sanitize, usage};
+use crate::format::format_price;
+use crate::providers::fetch_prices;
+use crate::state::{http_client, storage, upstream, Portfolios, SymbolCacheContainer};
+
+#[command]
+async fn add(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "portfolio add <symbol> <quantity>").await;
+    if !check_arg_count(ctx, msg, &args, 2..=2, &usage).await? {
+        return Ok(());
+    }
+    let (symbol, quantity) = match (args.single::<String>(), args.single::<f64>()) {
+        (Ok(symbol), Ok(quantity)) => (normalize_symbol(&symbol), quantity),
+        _ => {
+            msg.reply(&ctx.http, &usage).await?;
+            return Ok(());
+        }
+    };
+    if !quantity.is_finite() || quantity <= 0.0 {
+        msg.reply(&ctx.http, "The quantity must be a positive number.").await?;
+        return Ok(());
+    }
+
+    let total = {
+        let mut data = ctx.data.write().await;
+        let known = data
+            .get::<SymbolCacheContainer>()
+            .map_or(false, |symbols| symbols.get(&symbol).is_some());
+        if !known {
+            drop(data);
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+
+        let portfolios = data.get_mut::<Portfolios>().expect("Expected Portfolios in TypeMap.");
+        let holding = portfolios.entry(msg.author.id).or_default().entry(symbol.clone()).or_insert(0.0);
+        *holding += quantity;
+        *holding
+    };
+    storage(ctx).await.set_holding(msg.author.id.0, &symbol, total)?;
+
+    msg.reply(&ctx.http, format!("Added {} {}, you now hold {}", quantity, symbol.to_uppercase(), total)).await?;
+    Ok(())
+}
+
+#[command]
+async fn remove(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "portfolio remove <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let removed = {
+        let mut data = ctx.data.write().await;
+        let portfolios = data.get_mut::<Portfolios>().expect("Expected Portfolios in TypeMap.");
+        let removed = match portfolios.get_mut(&msg.author.id) {
+            Some(holdings) => {
+                let removed = holdings.remove(&symbol).is_some();
+                if holdings.is_empty() {
+                    portfolios.remove(&msg.author.id);
+                }
+                removed
+            },
+            None => false,
+        };
+        removed
+    };
+
+    if removed {
+        storage(ctx).await.remove_holding(msg.author.id.0, &symbol)?;
+        msg.reply(&ctx.http, format!("Removed {} from your portfolio", symbol.to_uppercase())).await?;
+    } else {
+        msg.reply(&ctx.http, format!("You don't hold any {}", symbol.to_uppercase())).await?;
+    }
+    Ok(())
+}
+
+/// `portfolio` on its own shows the caller's holdings, like `portfolio show`.
+#[command]
+#[sub_commands(add, remove, show)]
+#[bucket = "api"]
+async fn portfolio(ctx: &Context, msg: &Message) -> CommandResult {
+    show_portfolio(ctx, msg).await
+}
+
+#[command]
+#[bucket = "api"]
+async fn show(ctx: &Context, msg: &Message) -> CommandResult {
+    show_portfolio(ctx, msg).await
+}
+
+/// Lists each holding at its current USD price, with the total, from one batched price request.
+async fn show_portfolio(ctx: &Context, msg: &Message) -> CommandResult {
+    let holdings: Vec<(String, String, f64)> = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        let portfolios = data.get::<Portfolios>().expect("Expected Portfolios in TypeMap.");
+        let mut holdings: Vec<_> = portfolios
+            .get(&msg.author.id)
+            .into_iter()
+            .flatten()
+            .filter_map(|(symbol, quantity)| symbols.get(symbol).map(|id| (symbol.clone(), id.to_string(), *quantity)))
+            .collect();
+        holdings.sort_by(|a, b| a.0.cmp(&b.0));
+        holdings
+    };
+    if holdings.is_empty() {
+        let reply = format!(
+            "Your portfolio is empty. Add holdings with `{}portfolio add <symbol> <quantity>`.",
+            command_prefix(ctx, msg).await
+        );
+        msg.reply(&ctx.http, reply).await?;
+        return Ok(());
+    }
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    let ids: Vec<String> = holdings.iter().map(|(_, id, _)| id.clone()).collect();
+    let prices = match fetch_prices(&client, &ids, "usd", &upstream).await {
+        Ok(prices) => prices,
+        Err(why) => {
+            error!(error = %why, "Failed to fetch portfolio prices");
+            msg.reply(&ctx.http, why.user_message("portfolio prices")).await?;
+            return Ok(());
+        }
+    };
+
+    let mut total = 0.0;
+    let mut lines = Vec::new();
+    for (symbol, id, quantity) in &holdings {
+        match prices.get(id) {
+            Some(price) => {
+                let subtotal = quantity * price;
+                total += subtotal;
+                lines.push(format!(
+                    "{:<6} {:>14} × {:>12} = {}",
+                    symbol.to_uppercase(),
+                    quantity,
+                    format_price(*price, "usd"),
+                    format_price(subtotal, "usd")
+                ));
+            },
+            None => lines.push(format!("{:<6} {:>14} × (price unavailable)", symbol.to_uppercase(), quantity)),
+        }
+    }
+    lines.push(format!("Total: {}", format_price(total, "usd")));
+
+    reply_chunked(ctx, msg, &format!("```\n{}\n```", lines.join("\n"))).await?;
+    Ok(())
+}
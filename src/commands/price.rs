@@ -0,0 +1,461 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::Timestamp;
+use serenity::prelude::*;
+use serenity::utils::Colour;
+use tracing::{error, instrument, warn};
+
+use super::ethereum::{gas_embed, GasPrices};
+use super::market::{compare_embed, compare_text, day_embed, day_text, market_embed, market_text};
+use super::{arg_count_message, command_prefix, message_locale, normalize_symbol, sanitize, usage};
+use crate::format::{format_price, format_significant};
+use crate::i18n::{t, Locale};
+use crate::providers::{
+    cached_price, eth_price_source, fetch_coingecko_price, fetch_with_retry, resolve_symbol, supported_currencies, CoinGecko,
+    EthPriceSource, Etherscan, FallbackProvider, MarketData, PriceError, PriceProvider, SymbolCache, Upstream,
+    ETHERSCAN_API_URL,
+};
+use crate::state::{
+    config, http_client, preferred_currency, storage, upstream, GuildSettings, GuildSettingsContainer, PriceCache,
+    SymbolCacheContainer, UserPreferences,
+};
+
+/// The ETH price in `currency` and when it was fetched, from the price cache
+/// or, once that's stale, Etherscan with CoinGecko as a fallback.
+pub async fn cached_eth_price(ctx: &Context, currency: &str) -> Result<(f64, Instant), PriceError> {
+    let config = config(ctx).await;
+    let cache = {
+        let data = ctx.data.read().await;
+        data.get::<PriceCache>().cloned().expect("Expected PriceCache in TypeMap.")
+    };
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    cached_price(&cache, currency, config.price_cache_ttl, || async {
+        let coingecko = CoinGecko { client: Arc::clone(&client), upstream: upstream.clone() };
+        match eth_price_source(&config, currency) {
+            EthPriceSource::Etherscan { api_key } => {
+                let etherscan = Etherscan {
+                    client: Arc::clone(&client),
+                    base_url: ETHERSCAN_API_URL.to_string(),
+                    api_key: api_key.to_string(),
+                    upstream,
+                };
+                FallbackProvider { providers: vec![&etherscan, &coingecko] }.eth_price(currency).await
+            },
+            EthPriceSource::CoinGecko => coingecko.eth_price(currency).await,
+        }
+    }).await
+}
+
+/// Looks up the ETH price for `!price` and `/price`, or the message to show the user instead.
+#[instrument(skip(ctx))]
+pub async fn eth_price_quote(ctx: &Context, currency: &str, locale: Locale) -> Result<PriceQuote, String> {
+    if !supported_currencies().contains(&currency) {
+        let options = supported_currencies().iter().map(|c| c.to_uppercase()).collect::<Vec<_>>().join(", ");
+        return Err(t("price.unsupported_currency", locale, &[("options", &options)]));
+    }
+
+    match cached_eth_price(ctx, currency).await {
+        Ok((price, fetched_at)) => Ok(PriceQuote {
+            symbol: "ETH".to_string(),
+            value: format_price(price, currency),
+            age: Some(fetched_at.elapsed()),
+            locale,
+        }),
+        Err(why) => {
+            error!(currency = %currency, error = %why, "Failed to fetch ETH price");
+            Err(why.localized_message(&t("price.what", locale, &[("symbol", "ETH")]), locale))
+        }
+    }
+}
+
+/// A formatted price, sent as an embed or, where embeds aren't allowed, as text.
+pub struct PriceQuote {
+    symbol: String,
+    value: String,
+    /// How old a cached price is; `None` for a fresh fetch.
+    age: Option<Duration>,
+    locale: Locale,
+}
+
+impl PriceQuote {
+    fn text(&self) -> String {
+        match self.age {
+            Some(age) => t(
+                "price.text_aged",
+                self.locale,
+                &[("symbol", &self.symbol), ("value", &self.value), ("age", &age.as_secs().to_string())],
+            ),
+            None => t("price.text", self.locale, &[("symbol", &self.symbol), ("value", &self.value)]),
+        }
+    }
+}
+
+const PRICE_COLOUR: Colour = Colour::from_rgb(0x62, 0x7e, 0xea);
+
+/// Renders any price-style reply with the shared branding: the price colour
+/// (market data overrides it with its gain/loss colour) and a timestamp.
+pub fn price_embed<'a>(e: &'a mut CreateEmbed, reply: &PriceReply<'_>) -> &'a mut CreateEmbed {
+    e.colour(PRICE_COLOUR).timestamp(Timestamp::now());
+    match reply {
+        PriceReply::Quote(quote) => quote_embed(e, quote),
+        PriceReply::Gas(prices) => gas_embed(e, prices),
+        PriceReply::Market(market) => market_embed(e, market),
+        PriceReply::Day(market) => day_embed(e, market),
+        PriceReply::Compare(a, b) => compare_embed(e, a, b),
+    }
+}
+
+fn quote_embed<'a>(e: &'a mut CreateEmbed, quote: &PriceQuote) -> &'a mut CreateEmbed {
+    e.title(t("price.title", quote.locale, &[("symbol", &quote.symbol)]))
+        .field(t("price.field", quote.locale, &[]), &quote.value, false);
+    if let Some(age) = quote.age {
+        e.footer(|f| f.text(t("price.updated", quote.locale, &[("age", &age.as_secs().to_string())])));
+    }
+    e
+}
+
+/// Whether the bot may post embeds in the channel `msg` came from. Assumes
+/// yes when the channel isn't cached, and always in DMs.
+fn can_embed(ctx: &Context, msg: &Message) -> bool {
+    if msg.guild_id.is_none() {
+        return true;
+    }
+    match ctx.cache.guild_channel(msg.channel_id) {
+        Some(channel) => channel
+            .permissions_for_user(&ctx.cache, ctx.cache.current_user_id())
+            .map_or(true, |permissions| permissions.embed_links()),
+        None => true,
+    }
+}
+
+/// Settings for the guild `msg` came from; DMs get the defaults.
+pub async fn guild_settings(ctx: &Context, msg: &Message) -> GuildSettings {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return GuildSettings::default(),
+    };
+    let data = ctx.data.read().await;
+    let settings = data.get::<GuildSettingsContainer>().expect("Expected GuildSettingsContainer in TypeMap.");
+    settings.get(&guild_id).copied().unwrap_or_default()
+}
+
+/// The payload of a price-style reply, renderable as an embed or as text.
+pub enum PriceReply<'a> {
+    Quote(&'a PriceQuote),
+    Gas(&'a GasPrices),
+    /// `!market`: price, change, market cap, volume and the day's range.
+    Market(&'a MarketData),
+    /// `!price24h`: price with the day's range and change.
+    Day(&'a MarketData),
+    Compare(&'a MarketData, &'a MarketData),
+}
+
+impl PriceReply<'_> {
+    fn text(&self) -> String {
+        match self {
+            PriceReply::Quote(quote) => quote.text(),
+            PriceReply::Gas(prices) => format!(
+                "Gas prices: safe {} gwei, proposed {} gwei, fast {} gwei",
+                prices.safe, prices.propose, prices.fast
+            ),
+            PriceReply::Market(market) => market_text(market),
+            PriceReply::Day(market) => day_text(market),
+            PriceReply::Compare(a, b) => compare_text(a, b),
+        }
+    }
+}
+
+/// Replies with an embed when the guild wants them and the bot may post
+/// them, otherwise with plain text.
+pub async fn send_price_reply(ctx: &Context, msg: &Message, settings: GuildSettings, data: PriceReply<'_>) -> CommandResult {
+    if settings.use_embeds && can_embed(ctx, msg) {
+        msg.channel_id.send_message(&ctx.http, |m| m.reference_message(msg).embed(|e| price_embed(e, &data))).await?;
+    } else {
+        msg.reply(&ctx.http, data.text()).await?;
+    }
+    Ok(())
+}
+
+#[command]
+#[aliases("price")]
+#[bucket = "price"]
+async fn eth_price(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let currency = match args.single::<String>() {
+        Ok(currency) => currency.to_lowercase(),
+        Err(_) => preferred_currency(ctx, msg.author.id).await,
+    };
+    match eth_price_quote(ctx, &currency, message_locale(ctx, msg)).await {
+        Ok(quote) => send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Quote(&quote)).await?,
+        Err(reason) => {
+            msg.reply(&ctx.http, reason).await?;
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[bucket = "api"]
+async fn token(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(ctx, usage(ctx, msg, "token <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    let currency = preferred_currency(ctx, msg.author.id).await;
+    let locale = message_locale(ctx, msg);
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    match fetch_coingecko_price(&client, &coin_id, &currency, &upstream).await {
+        Ok(price) => {
+            let quote = PriceQuote {
+                symbol: symbol.to_uppercase(),
+                value: format_price(price, &currency),
+                age: None,
+                locale,
+            };
+            send_price_reply(ctx, msg, guild_settings(ctx, msg).await, PriceReply::Quote(&quote)).await?;
+        },
+        Err(why) => {
+            error!(coin_id = %coin_id, error = %why, "Failed to fetch token price");
+            let what = t("price.what", locale, &[("symbol", &symbol.to_uppercase())]);
+            msg.reply(&ctx.http, why.localized_message(&what, locale)).await?;
+        }
+    }
+    Ok(())
+}
+
+#[command]
+async fn setcurrency(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let currency = match args.single::<String>() {
+        Ok(currency) => currency.to_lowercase(),
+        Err(_) => {
+            msg.reply(&ctx.http, usage(ctx, msg, "setcurrency <code>").await).await?;
+            return Ok(());
+        }
+    };
+    if !supported_currencies().contains(&currency.as_str()) {
+        let options = supported_currencies().iter().map(|c| c.to_uppercase()).collect::<Vec<_>>().join(", ");
+        msg.reply(&ctx.http, format!("Unsupported currency. Valid options: {}", options)).await?;
+        return Ok(());
+    }
+
+    storage(ctx).await.set_user_currency(msg.author.id.0, &currency)?;
+    {
+        let mut data = ctx.data.write().await;
+        let preferences = data.get_mut::<UserPreferences>().expect("Expected UserPreferences in TypeMap.");
+        preferences.insert(msg.author.id, currency.clone());
+    }
+
+    msg.reply(&ctx.http, format!("Default currency set to {}.", currency.to_uppercase())).await?;
+    Ok(())
+}
+
+#[command]
+async fn mycurrency(ctx: &Context, msg: &Message) -> CommandResult {
+    let chosen = {
+        let data = ctx.data.read().await;
+        let preferences = data.get::<UserPreferences>().expect("Expected UserPreferences in TypeMap.");
+        preferences.get(&msg.author.id).cloned()
+    };
+    let reply = match chosen {
+        Some(currency) => format!("Your default currency is {}.", currency.to_uppercase()),
+        None => format!(
+            "You haven't set a default currency, so prices are in {}. Change it with `{}setcurrency <code>`.",
+            config(ctx).await.default_currency.to_uppercase(),
+            command_prefix(ctx, msg).await
+        ),
+    };
+    msg.reply(&ctx.http, reply).await?;
+    Ok(())
+}
+
+/// Either side of a `convert`: a fiat code or a CoinGecko coin id.
+enum Asset {
+    Fiat(String),
+    Coin(String),
+}
+
+/// Resolves a user-supplied symbol to a fiat currency or a known coin.
+fn resolve_asset(symbols: &SymbolCache, symbol: &str) -> Option<Asset> {
+    if supported_currencies().contains(&symbol) {
+        Some(Asset::Fiat(symbol.to_string()))
+    } else {
+        symbols.get(symbol).map(|id| Asset::Coin(id.to_string()))
+    }
+}
+
+/// Prices each asset in USD with a single CoinGecko request. Fiat rates are
+/// derived by pricing bitcoin in both USD and the fiat currency.
+async fn fetch_usd_rates(client: &reqwest::Client, assets: &[&Asset], upstream: &Upstream) -> Result<Vec<f64>, PriceError> {
+    let mut ids = vec!["bitcoin".to_string()];
+    let mut currencies = vec!["usd".to_string()];
+    for asset in assets {
+        match asset {
+            Asset::Coin(id) if !ids.contains(id) => ids.push(id.clone()),
+            Asset::Fiat(code) if !currencies.contains(code) => currencies.push(code.clone()),
+            _ => {},
+        }
+    }
+
+    let url = format!(
+        "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}",
+        ids.join(","),
+        currencies.join(",")
+    );
+    let json: Value = fetch_with_retry(client, &url, upstream, |body| Ok(serde_json::from_str(body)?)).await?;
+
+    assets
+        .iter()
+        .map(|asset| match asset {
+            Asset::Coin(id) => json[id]["usd"].as_f64().ok_or(PriceError::MissingField("usd")),
+            Asset::Fiat(code) if code == "usd" => Ok(1.0),
+            Asset::Fiat(code) => {
+                let btc_usd = json["bitcoin"]["usd"].as_f64().ok_or(PriceError::MissingField("usd"))?;
+                let btc_fiat = json["bitcoin"][code].as_f64().ok_or(PriceError::MissingField("price"))?;
+                Ok(btc_usd / btc_fiat)
+            }
+        })
+        .collect()
+}
+
+/// The amount and symbols of `!convert <amount> <from> <to>`, or the message to show the user instead.
+fn parse_convert_args(args: &mut Args, usage: &str) -> Result<(f64, String, String), String> {
+    if let Some(message) = arg_count_message(args.len(), &(3..=3), usage) {
+        return Err(message);
+    }
+    let raw_amount = args.current().unwrap_or_default().to_string();
+    let amount = match args.single::<f64>() {
+        Ok(amount) if amount.is_finite() => amount,
+        _ => return Err(format!("Invalid amount: `{}`", raw_amount)),
+    };
+    if amount == 0.0 {
+        return Err("0 of anything is still 0.".to_string());
+    }
+    if amount < 0.0 {
+        return Err("The amount to convert must be positive.".to_string());
+    }
+
+    match (args.single::<String>(), args.single::<String>()) {
+        (Ok(from), Ok(to)) => Ok((amount, normalize_symbol(&from), normalize_symbol(&to))),
+        _ => Err(usage.to_string()),
+    }
+}
+
+#[command]
+#[bucket = "api"]
+async fn convert(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let usage = usage(ctx, msg, "convert <amount> <from> <to>").await;
+    let (amount, from, to) = match parse_convert_args(&mut args, &usage) {
+        Ok(parsed) => parsed,
+        Err(why) => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &why)).await?;
+            return Ok(());
+        }
+    };
+
+    let assets = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        (resolve_asset(symbols, &from), resolve_asset(symbols, &to))
+    };
+    let (from_asset, to_asset) = match assets {
+        (Some(from_asset), Some(to_asset)) => (from_asset, to_asset),
+        (None, _) => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown currency: {}", from))).await?;
+            return Ok(());
+        },
+        (_, None) => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown currency: {}", to))).await?;
+            return Ok(());
+        }
+    };
+
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    match fetch_usd_rates(&client, &[&from_asset, &to_asset], &upstream).await {
+        Ok(rates) if rates[0] == 0.0 || rates[1] == 0.0 => {
+            let unpriced = if rates[0] == 0.0 { &from } else { &to };
+            warn!(symbol = %unpriced, "Conversion rate came back as zero");
+            msg.reply(&ctx.http, format!("Couldn't get a usable price for {} right now", unpriced.to_uppercase())).await?;
+        },
+        Ok(rates) => {
+            let converted = amount * rates[0] / rates[1];
+            let reply = format!(
+                "{} {} = {} {}",
+                amount,
+                from.to_uppercase(),
+                format_significant(converted),
+                to.to_uppercase()
+            );
+            msg.reply(&ctx.http, reply).await?;
+        },
+        Err(why) => {
+            error!(from = %from, to = %to, error = %why, "Failed to fetch conversion rates");
+            msg.reply(&ctx.http, why.user_message("conversion rates")).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::framework::standard::Delimiter;
+
+    use super::*;
+
+    fn parse(input: &str) -> Result<(f64, String, String), String> {
+        let mut args = Args::new(input, &[Delimiter::Multiple(", ".to_string()), Delimiter::Single(' ')]);
+        parse_convert_args(&mut args, "Usage: !convert <amount> <from> <to>")
+    }
+
+    #[test]
+    fn well_formed_arguments_are_parsed() {
+        assert_eq!(parse("2.5 ETH usd"), Ok((2.5, "eth".to_string(), "usd".to_string())));
+        assert_eq!(parse("10, btc, eur"), Ok((10.0, "btc".to_string(), "eur".to_string())));
+    }
+
+    #[test]
+    fn wrong_argument_count_shows_usage() {
+        assert_eq!(
+            parse("2.5 eth"),
+            Err("Expected 3 argument(s) but got 2. Usage: !convert <amount> <from> <to>".to_string())
+        );
+        assert_eq!(
+            parse("1 eth usd btc"),
+            Err("Expected 3 argument(s) but got 4. Usage: !convert <amount> <from> <to>".to_string())
+        );
+    }
+
+    #[test]
+    fn malformed_amounts_are_rejected() {
+        assert_eq!(parse("abc eth usd"), Err("Invalid amount: `abc`".to_string()));
+        assert_eq!(parse("inf eth usd"), Err("Invalid amount: `inf`".to_string()));
+        assert_eq!(parse("NaN eth usd"), Err("Invalid amount: `NaN`".to_string()));
+        assert_eq!(parse("0 eth usd"), Err("0 of anything is still 0.".to_string()));
+        assert_eq!(parse("-3 eth usd"), Err("The amount to convert must be positive.".to_string()));
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::MessageId;
+use serenity::model::Timestamp;
+use serenity::prelude::*;
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+use super::{normalize_symbol, sanitize, usage};
+use crate::format::format_price;
+use crate::providers::{fetch_prices, resolve_symbol};
+use crate::state::{http_client, upstream, LiveTicker, LiveTickers, SymbolCacheContainer};
+
+const TICKER_REFRESH: Duration = Duration::from_secs(30);
+const TICKER_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+fn ticker_embed<'a>(e: &'a mut CreateEmbed, symbol: &str, price: Option<f64>, stopped: bool) -> &'a mut CreateEmbed {
+    let title = if stopped {
+        format!("{} (stopped)", symbol.to_uppercase())
+    } else {
+        symbol.to_uppercase()
+    };
+    e.title(title)
+        .description(price.map_or("Price unavailable".to_string(), |price| format_price(price, "usd")))
+        .timestamp(Timestamp::now())
+}
+
+async fn ticker_price(ctx: &Context, coin_id: &str) -> Option<f64> {
+    let upstream = upstream(ctx).await;
+    let client = http_client(ctx).await;
+    match fetch_prices(&client, &[coin_id.to_string()], "usd", &upstream).await {
+        Ok(prices) => prices.get(coin_id).copied(),
+        Err(why) => {
+            warn!(coin_id, error = %why, "Failed to refresh ticker price");
+            None
+        }
+    }
+}
+
+/// Edits `message` with a fresh price every `TICKER_REFRESH` until stopped,
+/// `TICKER_LIFETIME` passes, or the message can no longer be edited.
+async fn run_ticker(ctx: Context, mut message: Message, symbol: String, coin_id: String, stop: Arc<Notify>) {
+    let deadline = tokio::time::Instant::now() + TICKER_LIFETIME;
+    let mut price = None;
+    loop {
+        tokio::select! {
+            _ = stop.notified() => break,
+            _ = tokio::time::sleep_until(deadline) => break,
+            _ = tokio::time::sleep(TICKER_REFRESH) => {},
+        }
+        price = ticker_price(&ctx, &coin_id).await.or(price);
+        let edited = message.edit(&ctx.http, |m| m.embed(|e| ticker_embed(e, &symbol, price, false))).await;
+        if let Err(why) = edited {
+            // Usually the message or its channel was deleted.
+            debug!(message_id = %message.id, error = ?why, "Stopping ticker");
+            break;
+        }
+    }
+
+    ctx.data.write().await.get_mut::<LiveTickers>().expect("Expected LiveTickers in TypeMap.").remove(&message.id);
+    let _ = message.edit(&ctx.http, |m| m.embed(|e| ticker_embed(e, &symbol, price, true))).await;
+}
+
+#[command]
+#[bucket = "api"]
+async fn ticker(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let symbol = match args.single::<String>().map(|raw| normalize_symbol(&raw)) {
+        Ok(symbol) if !symbol.is_empty() => symbol,
+        _ => {
+            msg.reply(&ctx.http, usage(ctx, msg, "ticker <symbol>").await).await?;
+            return Ok(());
+        }
+    };
+    let coin_id = {
+        let data = ctx.data.read().await;
+        let symbols = data.get::<SymbolCacheContainer>().expect("Expected SymbolCacheContainer in TypeMap.");
+        resolve_symbol(symbols, &symbol)
+    };
+    let coin_id = match coin_id {
+        Some(coin_id) => coin_id,
+        None => {
+            msg.reply(&ctx.http, sanitize(ctx, msg, &format!("Unknown token: {}", symbol))).await?;
+            return Ok(());
+        }
+    };
+
+    let price = ticker_price(ctx, &coin_id).await;
+    let message = msg.channel_id.send_message(&ctx.http, |m| m.embed(|e| ticker_embed(e, &symbol, price, false))).await?;
+    let stop = Arc::new(Notify::new());
+    {
+        let mut data = ctx.data.write().await;
+        let tickers = data.get_mut::<LiveTickers>().expect("Expected LiveTickers in TypeMap.");
+        tickers.insert(message.id, LiveTicker {
+            owner: msg.author.id,
+            stop: Arc::clone(&stop),
+        });
+    }
+    tokio::spawn(run_ticker(ctx.clone(), message, symbol, coin_id, stop));
+    Ok(())
+}
+
+#[command]
+async fn unticker(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let message_id = match args.single::<u64>() {
+        Ok(id) => MessageId(id),
+        Err(_) => {
+            msg.reply(&ctx.http, usage(ctx, msg, "unticker <message id>").await).await?;
+            return Ok(());
+        }
+    };
+
+    let stopped = {
+        let data = ctx.data.read().await;
+        let tickers = data.get::<LiveTickers>().expect("Expected LiveTickers in TypeMap.");
+        match tickers.get(&message_id) {
+            Some(ticker) if ticker.owner == msg.author.id => {
+                ticker.stop.notify_one();
+                true
+            },
+            _ => false,
+        }
+    };
+    if !stopped {
+        msg.reply(&ctx.http, "You have no running ticker on that message.").await?;
+    }
+    Ok(())
+}
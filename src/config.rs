@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+const DEFAULT_PREFIX: &str = "!";
+const DEFAULT_ARG_DELIMITERS: &[&str] = &[", ", " "];
+const DEFAULT_CURRENCY: &str = "usd";
+const DEFAULT_PRICE_CACHE_TTL_SECS: u64 = 30;
+const DEFAULT_COMMAND_COUNTER_PATH: &str = "command_counter.json";
+const DEFAULT_COMMAND_COUNTER_FLUSH_SECS: u64 = 60;
+const DEFAULT_GUILD_PREFIXES_PATH: &str = "guild_prefixes.json";
+const DEFAULT_PORTFOLIOS_PATH: &str = "portfolios.json";
+const DEFAULT_GUILD_SETTINGS_PATH: &str = "guild_settings.json";
+const DEFAULT_ALLOWED_CHANNELS_PATH: &str = "allowed_channels.json";
+const DEFAULT_ALERTS_DB_PATH: &str = "alerts.db";
+const DEFAULT_DATABASE_PATH: &str = "bot.db";
+const DEFAULT_ALERT_POLL_SECS: u64 = 60;
+const DEFAULT_HEALTH_BIND_ADDR: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const DEFAULT_HEALTH_PORT: u16 = 8080;
+const DEFAULT_STARTUP_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_HTTP_MAX_RETRIES: u32 = 3;
+const DEFAULT_HTTP_RETRY_BASE_DELAY_MS: u64 = 500;
+const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+const DEFAULT_BREAKER_COOLDOWN_SECS: u64 = 30;
+const DEFAULT_SUMMARY_TIME: DailyTime = DailyTime { hour: 0, minute: 0 };
+
+/// How upstream requests are retried on 429 and 5xx responses, and when an
+/// API that keeps failing is left alone for a while.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Consecutive failures that open an API's circuit breaker; zero turns it off.
+    pub breaker_threshold: u32,
+    /// How long an open breaker refuses requests before probing the API again.
+    pub breaker_cooldown: Duration,
+}
+
+/// How many gateway shards to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sharding {
+    Single,
+    Fixed(u64),
+    /// Use the shard count Discord recommends.
+    Auto,
+}
+
+impl FromStr for Sharding {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        if raw.eq_ignore_ascii_case("auto") {
+            return Ok(Sharding::Auto);
+        }
+        match raw.parse::<u64>() {
+            Ok(0) => Err("must be at least 1".to_string()),
+            Ok(1) => Ok(Sharding::Single),
+            Ok(count) => Ok(Sharding::Fixed(count)),
+            Err(_) => Err("expected a shard count or \"auto\"".to_string()),
+        }
+    }
+}
+
+/// A time of day in UTC, written `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyTime {
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl DailyTime {
+    pub fn seconds_into_day(self) -> u64 {
+        u64::from(self.hour * 60 + self.minute) * 60
+    }
+}
+
+impl FromStr for DailyTime {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let parsed = raw
+            .split_once(':')
+            .and_then(|(hour, minute)| Some((hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?)));
+        match parsed {
+            Some((hour, minute)) if hour < 24 && minute < 60 => Ok(DailyTime { hour, minute }),
+            _ => Err("expected a UTC time as HH:MM".to_string()),
+        }
+    }
+}
+
+/// Limits for one command bucket; zero leaves that limit off.
+#[derive(Debug, Clone, Copy)]
+pub struct BucketConfig {
+    pub delay: u64,
+    pub limit: u32,
+    pub time_span: u64,
+}
+
+const DEFAULT_EMOJI_BUCKET: BucketConfig = BucketConfig { delay: 5, limit: 0, time_span: 0 };
+const DEFAULT_COMPLICATED_BUCKET: BucketConfig = BucketConfig { delay: 5, limit: 2, time_span: 30 };
+const DEFAULT_PRICE_BUCKET: BucketConfig = BucketConfig { delay: 0, limit: 3, time_span: 60 };
+const DEFAULT_API_BUCKET: BucketConfig = BucketConfig { delay: 3, limit: 0, time_span: 0 };
+
+/// Settings read from the environment (or `.env`) once at startup.
+#[derive(Debug)]
+pub struct Config {
+    pub discord_token: String,
+    pub etherscan_api_key: Option<String>,
+    pub command_prefix: String,
+    /// Strings that separate command arguments; `ARG_DELIMITERS` lists them split by `|`.
+    pub arg_delimiters: Vec<String>,
+    /// Whether whitespace is allowed between the prefix, group and command names.
+    pub arg_with_whitespace: bool,
+    pub price_cache_ttl: Duration,
+    pub default_currency: String,
+    /// SQLite database holding portfolios, guild settings and command counts.
+    pub database_path: PathBuf,
+    /// The JSON files below predate `database_path`; they are imported when
+    /// the database is first created.
+    pub command_counter_path: PathBuf,
+    pub command_counter_flush_interval: Duration,
+    pub guild_prefixes_path: PathBuf,
+    pub portfolios_path: PathBuf,
+    pub guild_settings_path: PathBuf,
+    pub allowed_channels: Vec<u64>,
+    /// When set, only these commands (by name or alias) are enabled.
+    pub enabled_commands: Option<HashSet<String>>,
+    pub allowed_channels_path: PathBuf,
+    /// Where alerts were kept before they moved into `database_path`; copied
+    /// in once if it exists.
+    pub alerts_db_path: PathBuf,
+    pub alert_poll_interval: Duration,
+    /// Channel the daily market summary is posted to; unset turns it off.
+    pub summary_channel: Option<u64>,
+    pub summary_time: DailyTime,
+    /// Tokens listed after ETH and BTC in the daily summary.
+    pub summary_tokens: Vec<String>,
+    pub retry: RetryPolicy,
+    pub health_addr: SocketAddr,
+    /// Where to serve `/metrics` on its own; it is always on `health_addr` too.
+    pub metrics_addr: Option<SocketAddr>,
+    pub emoji_bucket: BucketConfig,
+    pub complicated_bucket: BucketConfig,
+    pub price_bucket: BucketConfig,
+    /// Per-user cooldown shared by the other commands that call upstream APIs.
+    pub api_bucket: BucketConfig,
+    pub sharding: Sharding,
+    /// How many times the gateway client is started, or application info
+    /// fetched, before giving up.
+    pub startup_max_attempts: u32,
+}
+
+/// Lists every missing or malformed variable, not just the first.
+#[derive(Debug)]
+pub struct ConfigError {
+    /// Each missing variable with how to describe it.
+    missing: Vec<(&'static str, &'static str)>,
+    invalid: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems = Vec::new();
+        for (key, description) in &self.missing {
+            problems.push(format!("{} is not set; create a .env file with {}=... or export it", description, key));
+        }
+        if !self.invalid.is_empty() {
+            problems.push(format!("invalid environment variables: {}", self.invalid.join("; ")));
+        }
+        f.write_str(&problems.join("\n"))
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Reads variables while collecting every problem found along the way.
+struct Env<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
+    missing: Vec<(&'static str, &'static str)>,
+    invalid: Vec<String>,
+}
+
+impl Env<'_> {
+    fn optional(&self, key: &'static str) -> Option<String> {
+        (self.lookup)(key).filter(|value| !value.trim().is_empty())
+    }
+
+    fn required_secret(&mut self, key: &'static str, file_key: &'static str, description: &'static str) -> String {
+        self.secret(key, file_key).unwrap_or_else(|| {
+            self.missing.push((key, description));
+            String::new()
+        })
+    }
+
+    /// Reads a secret from the file named by `file_key` (as Docker secrets are
+    /// mounted), falling back to `key` itself.
+    fn secret(&mut self, key: &'static str, file_key: &'static str) -> Option<String> {
+        let path = match self.optional(file_key) {
+            Some(path) => path,
+            None => return self.optional(key),
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let value = contents.trim_end_matches(&['\r', '\n'][..]);
+                Some(value.to_string()).filter(|value| !value.is_empty())
+            },
+            Err(why) => {
+                self.invalid.push(format!("{}={:?} ({})", file_key, path, why));
+                None
+            }
+        }
+    }
+
+    fn or(&self, key: &'static str, default: &str) -> String {
+        self.optional(key).unwrap_or_else(|| default.to_string())
+    }
+
+    fn parse<T>(&mut self, key: &'static str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        match self.optional(key) {
+            Some(raw) => raw.trim().parse().unwrap_or_else(|why| {
+                self.invalid.push(format!("{}={:?} ({})", key, raw, why));
+                default
+            }),
+            None => default,
+        }
+    }
+
+    fn list<T>(&mut self, key: &'static str) -> Vec<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let raw = self.optional(key).unwrap_or_default();
+        let mut values = Vec::new();
+        for item in raw.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            match item.parse() {
+                Ok(value) => values.push(value),
+                Err(why) => self.invalid.push(format!("{}: {:?} ({})", key, item, why)),
+            }
+        }
+        values
+    }
+
+    /// Reads a bucket from `[delay, limit, time_span]` keys, in seconds where applicable.
+    fn bucket(&mut self, keys: [&'static str; 3], default: BucketConfig) -> BucketConfig {
+        BucketConfig {
+            delay: self.parse(keys[0], default.delay),
+            limit: self.parse(keys[1], default.limit),
+            time_span: self.parse(keys[2], default.time_span),
+        }
+    }
+
+    fn secs(&mut self, key: &'static str, default: u64) -> Duration {
+        Duration::from_secs(self.parse(key, default))
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Result<Config, ConfigError> {
+        Config::from_lookup(|key| dotenv::var(key).ok())
+    }
+
+    /// Like `from_env`, but reads each variable with `lookup`.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let mut env = Env {
+            lookup: &lookup,
+            missing: Vec::new(),
+            invalid: Vec::new(),
+        };
+
+        let discord_token = env.required_secret("DISCORD_TOKEN", "DISCORD_TOKEN_FILE", "DISCORD_TOKEN (or DISCORD_TOKEN_FILE)");
+        let etherscan_api_key = env.secret("ETHERSCAN_API_KEY", "ETHERSCAN_API_KEY_FILE");
+        let command_prefix = env.or("COMMAND_PREFIX", DEFAULT_PREFIX);
+        // Split by hand rather than with `list`, which trims away a lone space delimiter.
+        let arg_delimiters: Vec<String> = match env.optional("ARG_DELIMITERS") {
+            Some(raw) => raw.split('|').filter(|delimiter| !delimiter.is_empty()).map(String::from).collect(),
+            None => DEFAULT_ARG_DELIMITERS.iter().map(|delimiter| delimiter.to_string()).collect(),
+        };
+        if arg_delimiters.is_empty() {
+            env.invalid.push("ARG_DELIMITERS (needs at least one delimiter)".to_string());
+        }
+        let arg_with_whitespace = env.parse("ARG_WITH_WHITESPACE", true);
+        let price_cache_ttl = env.secs("PRICE_CACHE_TTL_SECS", DEFAULT_PRICE_CACHE_TTL_SECS);
+        let default_currency = env.or("DEFAULT_CURRENCY", DEFAULT_CURRENCY).to_lowercase();
+        if !crate::providers::supported_currencies().contains(&default_currency.as_str()) {
+            env.invalid.push(format!("DEFAULT_CURRENCY={:?} (not a supported currency)", default_currency));
+        }
+        let database_path = PathBuf::from(env.or("DATABASE_PATH", DEFAULT_DATABASE_PATH));
+        let command_counter_path = PathBuf::from(env.or("COMMAND_COUNTER_PATH", DEFAULT_COMMAND_COUNTER_PATH));
+        let command_counter_flush_interval = env.secs("COMMAND_COUNTER_FLUSH_SECS", DEFAULT_COMMAND_COUNTER_FLUSH_SECS);
+        if command_counter_flush_interval.is_zero() {
+            env.invalid.push("COMMAND_COUNTER_FLUSH_SECS (must be at least 1)".to_string());
+        }
+        let guild_prefixes_path = PathBuf::from(env.or("GUILD_PREFIXES_PATH", DEFAULT_GUILD_PREFIXES_PATH));
+        let portfolios_path = PathBuf::from(env.or("PORTFOLIOS_PATH", DEFAULT_PORTFOLIOS_PATH));
+        let guild_settings_path = PathBuf::from(env.or("GUILD_SETTINGS_PATH", DEFAULT_GUILD_SETTINGS_PATH));
+        let allowed_channels = env.list("ALLOWED_CHANNEL_IDS");
+        let enabled_commands = env
+            .optional("ENABLED_COMMANDS")
+            .map(|_| env.list::<String>("ENABLED_COMMANDS").into_iter().map(|name| name.to_lowercase()).collect());
+        let allowed_channels_path = PathBuf::from(env.or("ALLOWED_CHANNELS_PATH", DEFAULT_ALLOWED_CHANNELS_PATH));
+        let alerts_db_path = PathBuf::from(env.or("ALERTS_DB_PATH", DEFAULT_ALERTS_DB_PATH));
+        let alert_poll_interval = env.secs("ALERT_POLL_SECS", DEFAULT_ALERT_POLL_SECS);
+        if alert_poll_interval.is_zero() {
+            env.invalid.push("ALERT_POLL_SECS (must be at least 1)".to_string());
+        }
+        let summary_channel = env.optional("SUMMARY_CHANNEL_ID").map(|_| env.parse("SUMMARY_CHANNEL_ID", 0));
+        let summary_time = env.parse("SUMMARY_TIME_UTC", DEFAULT_SUMMARY_TIME);
+        let summary_tokens = env
+            .list::<String>("SUMMARY_TOKENS")
+            .iter()
+            .map(|symbol| crate::commands::normalize_symbol(symbol))
+            .collect();
+        let retry = RetryPolicy {
+            max_retries: env.parse("HTTP_MAX_RETRIES", DEFAULT_HTTP_MAX_RETRIES),
+            base_delay: Duration::from_millis(env.parse("HTTP_RETRY_BASE_DELAY_MS", DEFAULT_HTTP_RETRY_BASE_DELAY_MS)),
+            breaker_threshold: env.parse("CIRCUIT_BREAKER_THRESHOLD", DEFAULT_BREAKER_THRESHOLD),
+            breaker_cooldown: env.secs("CIRCUIT_BREAKER_COOLDOWN_SECS", DEFAULT_BREAKER_COOLDOWN_SECS),
+        };
+        let health_addr = SocketAddr::new(
+            env.parse("HEALTH_BIND_ADDR", DEFAULT_HEALTH_BIND_ADDR),
+            env.parse("HEALTH_PORT", DEFAULT_HEALTH_PORT),
+        );
+        let metrics_addr = env
+            .optional("METRICS_PORT")
+            .map(|_| SocketAddr::new(health_addr.ip(), env.parse("METRICS_PORT", 0)));
+        if metrics_addr.map_or(false, |addr| addr.port() == health_addr.port()) {
+            env.invalid.push("METRICS_PORT (must differ from HEALTH_PORT)".to_string());
+        }
+        let emoji_bucket = env.bucket(
+            ["EMOJI_BUCKET_DELAY_SECS", "EMOJI_BUCKET_LIMIT", "EMOJI_BUCKET_TIME_SPAN_SECS"],
+            DEFAULT_EMOJI_BUCKET,
+        );
+        let complicated_bucket = env.bucket(
+            ["COMPLICATED_BUCKET_DELAY_SECS", "COMPLICATED_BUCKET_LIMIT", "COMPLICATED_BUCKET_TIME_SPAN_SECS"],
+            DEFAULT_COMPLICATED_BUCKET,
+        );
+        let price_bucket = env.bucket(
+            ["PRICE_BUCKET_DELAY_SECS", "PRICE_BUCKET_LIMIT", "PRICE_BUCKET_TIME_SPAN_SECS"],
+            DEFAULT_PRICE_BUCKET,
+        );
+        let api_bucket = env.bucket(
+            ["API_BUCKET_DELAY_SECS", "API_BUCKET_LIMIT", "API_BUCKET_TIME_SPAN_SECS"],
+            DEFAULT_API_BUCKET,
+        );
+        let sharding = env.parse("SHARD_COUNT", Sharding::Single);
+        let startup_max_attempts = env.parse("STARTUP_MAX_ATTEMPTS", DEFAULT_STARTUP_MAX_ATTEMPTS).max(1);
+
+        if !env.missing.is_empty() || !env.invalid.is_empty() {
+            return Err(ConfigError {
+                missing: env.missing,
+                invalid: env.invalid,
+            });
+        }
+
+        Ok(Config {
+            discord_token,
+            etherscan_api_key,
+            command_prefix,
+            arg_delimiters,
+            arg_with_whitespace,
+            price_cache_ttl,
+            default_currency,
+            database_path,
+            command_counter_path,
+            command_counter_flush_interval,
+            guild_prefixes_path,
+            portfolios_path,
+            guild_settings_path,
+            allowed_channels,
+            enabled_commands,
+            allowed_channels_path,
+            alerts_db_path,
+            alert_poll_interval,
+            summary_channel,
+            summary_time,
+            summary_tokens,
+            retry,
+            health_addr,
+            metrics_addr,
+            emoji_bucket,
+            complicated_bucket,
+            price_bucket,
+            api_bucket,
+            sharding,
+            startup_max_attempts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_error(vars: &[(&str, &str)]) -> String {
+        Config::from_lookup(|key| vars.iter().find(|(name, _)| *name == key).map(|(_, value)| value.to_string()))
+            .err()
+            .expect("config should be rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn zero_command_counter_flush_interval_is_rejected() {
+        let why = config_error(&[("DISCORD_TOKEN", "token"), ("COMMAND_COUNTER_FLUSH_SECS", "0")]);
+        assert!(why.contains("COMMAND_COUNTER_FLUSH_SECS (must be at least 1)"), "{}", why);
+    }
+
+    #[test]
+    fn zero_alert_poll_interval_is_rejected() {
+        let why = config_error(&[("DISCORD_TOKEN", "token"), ("ALERT_POLL_SECS", "0")]);
+        assert!(why.contains("ALERT_POLL_SECS (must be at least 1)"), "{}", why);
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::Duration;
+
+fn currency_symbol(currency: &str) -> &'static str {
+    match currency {
+        "usd" | "cad" | "aud" => "$",
+        "eur" => "€",
+        "gbp" => "£",
+        "jpy" => "¥",
+        "chf" => "CHF ",
+        "btc" => "₿",
+        _ => "",
+    }
+}
+
+/// Formats an amount in `currency`, keeping extra precision for sub-unit prices.
+pub fn format_price(price: f64, currency: &str) -> String {
+    let symbol = currency_symbol(currency);
+    if price >= 1.0 {
+        format!("{}{:.2}", symbol, price)
+    } else {
+        format!("{}{:.6}", symbol, price)
+    }
+}
+
+/// Formats large values to cents and small ones to six significant figures.
+pub fn format_significant(value: f64) -> String {
+    let magnitude = value.abs();
+    let decimals = if magnitude >= 1000.0 || magnitude == 0.0 {
+        2
+    } else {
+        (5 - magnitude.log10().floor() as i32).clamp(2, 12) as usize
+    };
+    format!("{:.*}", decimals, value)
+}
+
+/// Formats `value` to two decimals with thousands separators, e.g. `1,234,567.89`.
+pub fn format_thousands(value: f64) -> String {
+    let formatted = format!("{:.2}", value.abs());
+    let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, "00"));
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{}{}.{}", sign, grouped, fraction)
+}
+
+/// Abbreviates large amounts with K/M/B/T suffixes, e.g. `1.23B`.
+pub fn format_large_number(n: f64) -> String {
+    const SUFFIXES: [(f64, &str); 4] = [(1e3, "K"), (1e6, "M"), (1e9, "B"), (1e12, "T")];
+    // Round before settling on a suffix, moving up while the mantissa reaches
+    // 1000, so 999,999 reads 1.00M rather than 1000.00K.
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+    let (mut mantissa, mut suffix) = (round(n), "");
+    for (scale, next) in SUFFIXES {
+        if mantissa.abs() < 1000.0 {
+            break;
+        }
+        mantissa = round(n / scale);
+        suffix = next;
+    }
+    format!("{:.2}{}", mantissa, suffix)
+}
+
+/// Formats a duration as e.g. `2d 3h 14m`.
+pub fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Discord rejects messages longer than this many characters.
+pub const MESSAGE_LIMIT: usize = 2000;
+
+const FENCE: &str = "```";
+
+/// Builds the messages for `split_message`, tracking the code block (if any)
+/// the next line falls in.
+struct MessageSplitter<'a> {
+    limit: usize,
+    chunks: Vec<String>,
+    chunk: String,
+    chunk_len: usize,
+    /// Whether `chunk` holds nothing but a reopened fence.
+    fresh: bool,
+    /// The line that opened the code block we're in.
+    fence: Option<&'a str>,
+    /// Where that line starts in `chunk`, while nothing has followed it.
+    fence_at: Option<usize>,
+}
+
+impl<'a> MessageSplitter<'a> {
+    /// Characters left in the current message. Room to close an open code
+    /// block is kept back unless `closing`, when the line is that close.
+    fn room(&self, closing: bool) -> usize {
+        let separator = usize::from(!self.chunk.is_empty());
+        let close = if self.fence.is_some() && !closing { FENCE.len() + 1 } else { 0 };
+        self.limit.saturating_sub(self.chunk_len + separator + close)
+    }
+
+    fn push(&mut self, text: &str) {
+        if !self.chunk.is_empty() {
+            self.chunk.push('\n');
+            self.chunk_len += 1;
+        }
+        self.chunk.push_str(text);
+        self.chunk_len += text.chars().count();
+        self.fresh = false;
+        self.fence_at = None;
+    }
+
+    /// Ends the current message, closing any open code block and reopening it in the next.
+    fn flush(&mut self) {
+        match self.fence_at.take() {
+            // A block that opens on the last line starts in the next message instead.
+            Some(at) => self.chunk.truncate(at.saturating_sub(1)),
+            None if self.fence.is_some() => {
+                self.chunk.push('\n');
+                self.chunk.push_str(FENCE);
+            },
+            None => {},
+        }
+        if !self.chunk.trim().is_empty() {
+            self.chunks.push(std::mem::take(&mut self.chunk));
+        }
+        self.chunk = self.fence.unwrap_or_default().to_string();
+        self.chunk_len = self.chunk.chars().count();
+        self.fresh = true;
+    }
+
+    fn line(&mut self, line: &'a str) {
+        let is_fence = line.trim_start().starts_with(FENCE);
+        let closing = is_fence && self.fence.is_some();
+        let mut rest = line;
+        while rest.chars().count() > self.room(closing) {
+            if !self.fresh {
+                self.flush();
+                continue;
+            }
+            // Too long for even an empty message, so break mid-line.
+            let at = rest.char_indices().nth(self.room(closing).max(1)).map_or(rest.len(), |(i, _)| i);
+            self.push(&rest[..at]);
+            self.flush();
+            rest = &rest[at..];
+        }
+        let start = self.chunk.len();
+        self.push(rest);
+        if closing {
+            self.fence = None;
+        } else if is_fence {
+            self.fence = Some(line);
+            self.fence_at = Some(start + usize::from(start > 0));
+        }
+    }
+}
+
+/// Splits `content` into messages of at most `limit` characters, breaking
+/// between lines where possible. A code block cut in two is closed at the
+/// end of one message and reopened at the start of the next.
+pub fn split_message(content: &str, limit: usize) -> Vec<String> {
+    let mut splitter = MessageSplitter {
+        limit,
+        chunks: Vec::new(),
+        chunk: String::new(),
+        chunk_len: 0,
+        fresh: true,
+        fence: None,
+        fence_at: None,
+    };
+    for line in content.split('\n') {
+        splitter.line(line);
+    }
+    // Leave a code block the content never closed as it is.
+    splitter.fence = None;
+    splitter.fence_at = None;
+    if !splitter.fresh {
+        splitter.flush();
+    }
+    splitter.chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_numbers_below_a_thousand_have_no_suffix() {
+        assert_eq!(format_large_number(0.0), "0.00");
+        assert_eq!(format_large_number(999.99), "999.99");
+    }
+
+    #[test]
+    fn large_numbers_take_the_suffix_they_round_to() {
+        assert_eq!(format_large_number(999.999), "1.00K");
+        assert_eq!(format_large_number(1_000.0), "1.00K");
+        assert_eq!(format_large_number(999_990.0), "999.99K");
+        assert_eq!(format_large_number(999_999.0), "1.00M");
+        assert_eq!(format_large_number(1_234_567_890.0), "1.23B");
+        assert_eq!(format_large_number(999_999_999_999.0), "1.00T");
+        assert_eq!(format_large_number(-999_999.0), "-1.00M");
+    }
+
+    #[test]
+    fn large_numbers_past_trillions_stay_in_trillions() {
+        assert_eq!(format_large_number(1.5e15), "1500.00T");
+    }
+
+    fn assert_within(chunks: &[String], limit: usize) {
+        for chunk in chunks {
+            assert!(chunk.chars().count() <= limit, "{} chars: {:?}", chunk.chars().count(), chunk);
+        }
+    }
+
+    #[test]
+    fn content_at_the_limit_is_one_message() {
+        let content = format!("{}\n{}", "a".repeat(5), "b".repeat(4));
+        assert_eq!(split_message(&content, 10), vec![content.clone()]);
+    }
+
+    #[test]
+    fn content_one_over_the_limit_breaks_between_lines() {
+        let content = format!("{}\n{}", "a".repeat(5), "b".repeat(4));
+        assert_eq!(split_message(&content, 9), vec!["a".repeat(5), "b".repeat(4)]);
+    }
+
+    #[test]
+    fn line_longer_than_the_limit_is_broken_mid_line() {
+        let chunks = split_message(&"x".repeat(25), 10);
+        assert_eq!(chunks, vec!["x".repeat(10), "x".repeat(10), "x".repeat(5)]);
+    }
+
+    #[test]
+    fn limit_counts_characters_not_bytes() {
+        let chunks = split_message(&"é".repeat(12), 10);
+        assert_eq!(chunks, vec!["é".repeat(10), "é".repeat(2)]);
+    }
+
+    #[test]
+    fn code_block_cut_in_two_is_closed_and_reopened() {
+        let content = format!("```\n{}\n{}\n```", "a".repeat(10), "b".repeat(10));
+        let chunks = split_message(&content, 20);
+        assert_within(&chunks, 20);
+        assert_eq!(chunks.len(), 2);
+        for chunk in &chunks {
+            assert!(chunk.starts_with("```\n") && chunk.ends_with("\n```"), "{:?}", chunk);
+        }
+    }
+
+    #[test]
+    fn chunks_never_pass_the_limit() {
+        let content = (0..200).map(|i| "word ".repeat(i % 37)).collect::<Vec<_>>().join("\n");
+        let content = format!("intro\n```\n{}\n```\nend", content);
+        let chunks = split_message(&content, MESSAGE_LIMIT);
+        assert!(chunks.len() > 1);
+        assert_within(&chunks, MESSAGE_LIMIT);
+    }
+}
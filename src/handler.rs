@@ -0,0 +1,134 @@
+use serde_json::Value;
+use serenity::async_trait;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::gateway::ConnectionStage;
+use serenity::model::application::command::{Command, CommandOptionType};
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::event::ResumedEvent;
+use serenity::model::gateway::Ready;
+use serenity::prelude::*;
+use tracing::{debug, error, info, warn};
+
+use crate::commands::ethereum::gas_prices;
+use crate::commands::price::{eth_price_quote, price_embed, PriceReply};
+use crate::commands::{command_names, GROUPS};
+use crate::i18n::Locale;
+use crate::providers::supported_currencies;
+use crate::state::{preferred_currency, BotStatusContainer};
+
+pub struct Handler;
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        let shard = ready.shard.map_or("0/1".to_string(), |[id, total]| format!("{}/{}", id, total));
+        info!(user = %ready.user.name, session_id = %ready.session_id, shard = %shard, "Connected to Discord");
+        info!(count = ready.guilds.len(), "Guilds available");
+        info!(commands = %command_names(GROUPS).join(","), "Commands registered");
+        if let Some(status) = ctx.data.read().await.get::<BotStatusContainer>() {
+            status.set_connected(true);
+        }
+
+        let price = Command::create_global_application_command(&ctx.http, |command| {
+            command
+                .name("price")
+                .description("Current ETH price")
+                .create_option(|option| {
+                    option
+                        .name("currency")
+                        .description("Currency to quote the price in")
+                        .kind(CommandOptionType::String)
+                        .required(false);
+                    for currency in supported_currencies() {
+                        option.add_string_choice(currency.to_uppercase(), *currency);
+                    }
+                    option
+                })
+        }).await;
+        let gas = Command::create_global_application_command(&ctx.http, |command| {
+            command.name("gas").description("Current Ethereum gas prices")
+        }).await;
+        for registered in [price, gas] {
+            if let Err(why) = registered {
+                error!(error = ?why, "Couldn't register slash command");
+            }
+        }
+    }
+
+    async fn resume(&self, ctx: Context, _: ResumedEvent) {
+        info!("Resumed gateway session");
+        if let Some(status) = ctx.data.read().await.get::<BotStatusContainer>() {
+            status.set_connected(true);
+        }
+    }
+
+    async fn shard_stage_update(&self, ctx: Context, event: ShardStageUpdateEvent) {
+        let connected = match event.new {
+            ConnectionStage::Connected => {
+                info!(shard = event.shard_id.0, from = %event.old, "Shard connected");
+                true
+            },
+            ConnectionStage::Disconnected => {
+                warn!(shard = event.shard_id.0, from = %event.old, "Shard disconnected");
+                false
+            },
+            stage => {
+                debug!(shard = event.shard_id.0, from = %event.old, to = %stage, "Shard stage changed");
+                return;
+            },
+        };
+        if let Some(status) = ctx.data.read().await.get::<BotStatusContainer>() {
+            status.set_connected(connected);
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::ApplicationCommand(command) = interaction {
+            let responded = match command.data.name.as_str() {
+                "price" => {
+                    let preferred = preferred_currency(&ctx, command.user.id).await;
+                    let currency = command
+                        .data
+                        .options
+                        .iter()
+                        .find(|option| option.name == "currency")
+                        .and_then(|option| option.value.as_ref())
+                        .and_then(Value::as_str)
+                        .map(str::to_lowercase)
+                        .unwrap_or(preferred);
+                    let quote = eth_price_quote(&ctx, &currency, Locale::from_tag(&command.locale)).await;
+                    command.create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| match &quote {
+                                Ok(quote) => message.embed(|e| price_embed(e, &PriceReply::Quote(quote))),
+                                Err(reason) => message.content(reason),
+                            })
+                    }).await
+                },
+                "gas" => {
+                    let prices = gas_prices(&ctx).await;
+                    command.create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| match &prices {
+                                Ok(prices) => message.embed(|e| price_embed(e, &PriceReply::Gas(prices))),
+                                Err(reason) => message.content(reason),
+                            })
+                    }).await
+                },
+                other => {
+                    let content = format!("Unknown command: {}", other);
+                    command.create_interaction_response(&ctx.http, |response| {
+                        response
+                            .kind(InteractionResponseType::ChannelMessageWithSource)
+                            .interaction_response_data(|message| message.content(content))
+                    }).await
+                },
+            };
+            if let Err(why) = responded {
+                warn!(command = %command.data.name, error = ?why, "Couldn't respond to slash command");
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hyper::server::conn::AddrIncoming;
+use hyper::server::Builder;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Request, Response, Server, StatusCode};
+use serde_json::json;
+use tracing::error;
+
+use crate::metrics::metrics;
+
+/// Liveness information shared between the bot and the health endpoint.
+pub struct BotStatus {
+    started: Instant,
+    connected: AtomicBool,
+    /// Unix time of the last (re)connect or resume; zero before the first.
+    last_connected: AtomicU64,
+    commands_processed: AtomicU64,
+}
+
+impl BotStatus {
+    pub fn new() -> BotStatus {
+        BotStatus {
+            started: Instant::now(),
+            connected: AtomicBool::new(false),
+            last_connected: AtomicU64::new(0),
+            commands_processed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+        if connected {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+            self.last_connected.store(now, Ordering::Relaxed);
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn record_command(&self) {
+        self.commands_processed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Binds `addr` for `serve` or `serve_metrics`, so a port that's in use is
+/// reported at startup rather than when the server is awaited.
+pub fn bind(addr: SocketAddr) -> Result<Builder<AddrIncoming>, hyper::Error> {
+    Server::try_bind(&addr)
+}
+
+/// Serves `GET /health` and `GET /metrics` on `listener` until `shutdown` resolves.
+pub async fn serve(
+    listener: Builder<AddrIncoming>,
+    status: Arc<BotStatus>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let status = Arc::clone(&status);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = respond(&request, &status);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    listener.serve(make_service).with_graceful_shutdown(shutdown).await
+}
+
+/// Serves only `GET /metrics` on `listener` until `shutdown` resolves, for
+/// scraping on a separate port from the health check.
+pub async fn serve_metrics(
+    listener: Builder<AddrIncoming>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|request: Request<Body>| async move {
+            let response = match request.uri().path() {
+                "/metrics" => prometheus_metrics(),
+                _ => not_found(),
+            };
+            Ok::<_, Infallible>(response)
+        }))
+    });
+
+    listener.serve(make_service).with_graceful_shutdown(shutdown).await
+}
+
+fn respond(request: &Request<Body>, status: &BotStatus) -> Response<Body> {
+    match request.uri().path() {
+        "/health" => health(status),
+        "/metrics" => prometheus_metrics(),
+        _ => not_found(),
+    }
+}
+
+fn not_found() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::empty())
+        .expect("static response is valid")
+}
+
+fn health(status: &BotStatus) -> Response<Body> {
+    let body = json!({
+        "uptime_secs": status.uptime().as_secs(),
+        "connected": status.connected.load(Ordering::Relaxed),
+        "last_connected": match status.last_connected.load(Ordering::Relaxed) {
+            0 => None,
+            secs => Some(secs),
+        },
+        "commands_processed": status.commands_processed.load(Ordering::Relaxed),
+    });
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("static response is valid")
+}
+
+fn prometheus_metrics() -> Response<Body> {
+    match metrics().encode() {
+        Ok((content_type, body)) => Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .expect("static response is valid"),
+        Err(why) => {
+            error!(error = %why, "Failed to encode metrics");
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .expect("static response is valid")
+        }
+    }
+}
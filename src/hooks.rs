@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serenity::framework::standard::macros::{check, hook};
+use serenity::framework::standard::{Args, CommandOptions, CommandResult, DispatchError, Reason};
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use serenity::prelude::*;
+use tokio::sync::oneshot;
+use tracing::{debug, error, info, warn};
+
+use crate::commands::{command_prefix, is_slow_command, Replied};
+use crate::metrics::metrics;
+use crate::panics;
+use crate::state::{
+    AllowedChannels, BotOwners, BotStatusContainer, CommandCounter, CommandFailureCounter, CommandTimings, TypingIndicators,
+    RECENT_FAILURES,
+};
+
+#[check]
+#[name = "Owner"]
+async fn owner_check(ctx: &Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> Result<(), Reason> {
+    let data = ctx.data.read().await;
+    let owners = data.get::<BotOwners>().expect("Expected BotOwners in TypeMap.");
+    if owners.contains(&msg.author.id) {
+        Ok(())
+    } else {
+        Err(Reason::User("You are not authorized to use this command.".to_string()))
+    }
+}
+
+/// An empty allow-list means commands are allowed everywhere.
+fn channel_allowed(allowed: &HashSet<ChannelId>, channel_id: ChannelId) -> bool {
+    allowed.is_empty() || allowed.contains(&channel_id)
+}
+
+#[check]
+#[name = "AllowedChannel"]
+async fn allowed_channel_check(ctx: &Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> Result<(), Reason> {
+    if msg.guild_id.is_none() {
+        return Ok(());
+    }
+    let data = ctx.data.read().await;
+    let allowed = data.get::<AllowedChannels>().expect("Expected AllowedChannels in TypeMap.");
+    if channel_allowed(allowed, msg.channel_id) {
+        Ok(())
+    } else {
+        // Reason::Log keeps `dispatch_error` quiet in disallowed channels.
+        Err(Reason::Log(format!("Commands are not allowed in channel {}", msg.channel_id)))
+    }
+}
+
+/// Commands that finish within this never show the typing indicator.
+const TYPING_DELAY: Duration = Duration::from_millis(500);
+
+/// Shows "typing…" in `channel_id` from `TYPING_DELAY` after a command
+/// starts until `stopped` fires or its sender is dropped.
+async fn show_typing(http: Arc<Http>, channel_id: ChannelId, mut stopped: oneshot::Receiver<()>) {
+    tokio::select! {
+        _ = &mut stopped => return,
+        _ = tokio::time::sleep(TYPING_DELAY) => {},
+    }
+    let typing = match channel_id.start_typing(&http) {
+        Ok(typing) => typing,
+        Err(why) => {
+            debug!(channel_id = %channel_id, error = %why, "Couldn't show typing indicator");
+            return;
+        }
+    };
+    let _ = stopped.await;
+    typing.stop();
+}
+
+#[hook]
+pub async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
+    info!(command = command_name, user_id = %msg.author.id, user = %msg.author.name, "Got command");
+    metrics().record_command(command_name);
+    let (data, msg_id) = (Arc::clone(&ctx.data), msg.id);
+    panics::command_started(command_name, move || {
+        // `after` never runs for a panicked command, so drop its typing
+        // indicator and timing here instead.
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let mut data = data.write().await;
+                if let Some(typing) = data.get_mut::<TypingIndicators>() {
+                    typing.remove(&msg_id);
+                }
+                if let Some(timings) = data.get_mut::<CommandTimings>() {
+                    timings.remove(&msg_id);
+                }
+            });
+        }
+    });
+
+    // Increment the number of times this command has been run once. If
+    // the command's name does not exist in the counter, add a default
+    // value of 0.
+    let mut data = ctx.data.write().await;
+    let counter = data.get_mut::<CommandCounter>().expect("Expected CommandCounter in TypeMap.");
+    let entry = counter.entry(command_name.to_string()).or_insert(0);
+    *entry += 1;
+
+    let timings = data.get_mut::<CommandTimings>().expect("Expected CommandTimings in TypeMap.");
+    timings.insert(msg.id, Instant::now());
+
+    if is_slow_command(command_name) {
+        let (stop, stopped) = oneshot::channel();
+        let typing = data.get_mut::<TypingIndicators>().expect("Expected TypingIndicators in TypeMap.");
+        typing.insert(msg.id, stop);
+        tokio::spawn(show_typing(Arc::clone(&ctx.http), msg.channel_id, stopped));
+    }
+
+    true // if `before` returns false, command processing doesn't happen.
+}
+
+#[hook]
+pub async fn after(ctx: &Context, msg: &Message, command_name: &str, command_result: CommandResult) {
+    panics::command_finished();
+    let started = {
+        let mut data = ctx.data.write().await;
+        if let Some(status) = data.get::<BotStatusContainer>() {
+            status.record_command();
+        }
+        if let Err(why) = &command_result {
+            let failures = data.get_mut::<CommandFailureCounter>().expect("Expected CommandFailureCounter in TypeMap.");
+            let entry = failures.entry(command_name.to_string()).or_default();
+            entry.count += 1;
+            if entry.recent.len() == RECENT_FAILURES {
+                entry.recent.pop_front();
+            }
+            entry.recent.push_back(why.to_string());
+        }
+        let typing = data.get_mut::<TypingIndicators>().expect("Expected TypingIndicators in TypeMap.");
+        typing.remove(&msg.id);
+        let timings = data.get_mut::<CommandTimings>().expect("Expected CommandTimings in TypeMap.");
+        timings.remove(&msg.id)
+    };
+    let elapsed = started.map(|started| started.elapsed());
+    metrics().record_command_result(command_name, elapsed, command_result.is_ok());
+    let latency_ms = elapsed.map(|elapsed| elapsed.as_millis() as u64).unwrap_or_default();
+
+    match command_result {
+        Ok(()) => info!(command = command_name, user_id = %msg.author.id, latency_ms, "Processed command"),
+        Err(why) => {
+            error!(command = command_name, user_id = %msg.author.id, latency_ms, error = ?why, "Command returned error");
+            if why.downcast_ref::<Replied>().is_none() {
+                if let Err(why) = msg.reply(ctx, "Something went wrong running that command.").await {
+                    warn!(command = command_name, error = %why, "Couldn't report command failure");
+                }
+            }
+        },
+    }
+}
+
+#[hook]
+pub async fn unknown_command(_ctx: &Context, _msg: &Message, unknown_command_name: &str) {
+    warn!(command = unknown_command_name, "Could not find command");
+}
+
+#[hook]
+pub async fn normal_message(_ctx: &Context, msg: &Message) {
+    debug!(content = %msg.content, "Message is not a command");
+}
+
+#[hook]
+pub async fn dynamic_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    Some(command_prefix(ctx, msg).await)
+}
+
+#[hook]
+pub async fn delay_action(ctx: &Context, msg: &Message) {
+    // You may want to handle a Discord rate limit if this fails.
+    let _ = msg.react(ctx, '⏱').await;
+}
+
+#[hook]
+pub async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError, command_name: &str) {
+    match error {
+        DispatchError::Ratelimited(info) => {
+            // We notify them only once.
+            if info.is_first_try {
+                // Round up so a sub-second wait isn't reported as 0 seconds.
+                let secs = info.rate_limit.as_secs_f64().ceil() as u64;
+                let plural = if secs == 1 { "" } else { "s" };
+                let _ = msg
+                    .reply(ctx, format!("`{}` is on cooldown, try again in {} second{}.", command_name, secs, plural))
+                    .await;
+            }
+        },
+        DispatchError::CheckFailed(_, Reason::User(reason)) => {
+            let _ = msg.reply(ctx, reason).await;
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_allow_list_allows_every_channel() {
+        assert!(channel_allowed(&HashSet::new(), ChannelId(1)));
+    }
+
+    #[test]
+    fn allow_list_allows_only_its_channels() {
+        let allowed = HashSet::from([ChannelId(1), ChannelId(2)]);
+        assert!(channel_allowed(&allowed, ChannelId(2)));
+        assert!(!channel_allowed(&allowed, ChannelId(3)));
+    }
+}
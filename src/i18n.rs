@@ -0,0 +1,113 @@
+/// Languages replies can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    /// The supported locale for a Discord locale tag such as `es-ES`, or English.
+    pub fn from_tag(tag: &str) -> Locale {
+        match tag.split('-').next().unwrap_or_default() {
+            "es" => Locale::Es,
+            _ => Locale::En,
+        }
+    }
+
+    fn strings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::Es => ES,
+        }
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    ("price.text", "The current price of {symbol} is {value}"),
+    ("price.text_aged", "The current price of {symbol} is {value} (as of {age}s ago)"),
+    ("price.title", "{symbol} Price"),
+    ("price.field", "Price"),
+    ("price.updated", "Updated {age}s ago"),
+    ("price.unsupported_currency", "Unsupported currency. Valid options: {options}"),
+    ("price.what", "{symbol} price"),
+    ("error.timeout", "Request timed out, try again."),
+    ("error.busy", "The data provider is busy right now, try again in a minute"),
+    ("error.rate_limited", "Rate limited by data provider, please try again in a minute"),
+    ("error.unavailable", "The data provider is temporarily unavailable, try again shortly."),
+    ("error.fetch", "Couldn't fetch {what} right now"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("price.text", "El precio actual de {symbol} es {value}"),
+    ("price.text_aged", "El precio actual de {symbol} es {value} (hace {age}s)"),
+    ("price.title", "Precio de {symbol}"),
+    ("price.field", "Precio"),
+    ("price.updated", "Actualizado hace {age}s"),
+    ("price.unsupported_currency", "Moneda no soportada. Opciones válidas: {options}"),
+    ("price.what", "el precio de {symbol}"),
+    ("error.timeout", "La solicitud tardó demasiado, inténtalo de nuevo."),
+    ("error.busy", "El proveedor de datos está ocupado, inténtalo de nuevo en un minuto"),
+    ("error.rate_limited", "El proveedor de datos limitó las solicitudes, inténtalo de nuevo en un minuto"),
+    ("error.unavailable", "El proveedor de datos no está disponible por ahora, inténtalo de nuevo en breve."),
+    ("error.fetch", "No se pudo obtener {what} en este momento"),
+];
+
+fn lookup(strings: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    strings.iter().find(|(k, _)| *k == key).map(|(_, text)| *text)
+}
+
+/// The template for `key` in `strings`, else in English, else `key` itself.
+fn template<'a>(strings: &'static [(&'static str, &'static str)], key: &'a str) -> &'a str {
+    lookup(strings, key).or_else(|| lookup(EN, key)).unwrap_or(key)
+}
+
+/// The text for `key` in `locale` (or English, if it has no translation)
+/// with each `{name}` placeholder filled from `args`.
+pub fn t(key: &str, locale: Locale, args: &[(&str, &str)]) -> String {
+    let template = template(locale.strings(), key);
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `{name}` placeholders in `text`, in order.
+    fn placeholders(text: &str) -> Vec<&str> {
+        text.split('{').skip(1).filter_map(|rest| rest.split_once('}').map(|(name, _)| name)).collect()
+    }
+
+    #[test]
+    fn every_key_is_translated_with_the_same_placeholders() {
+        for locale in [Locale::En, Locale::Es] {
+            for (key, _) in EN.iter().chain(ES) {
+                let text = lookup(locale.strings(), key).unwrap_or_else(|| panic!("{:?} has no {}", locale, key));
+                assert_eq!(placeholders(text), placeholders(lookup(EN, key).unwrap()), "{:?} {}", locale, key);
+            }
+        }
+    }
+
+    #[test]
+    fn placeholders_are_filled() {
+        assert_eq!(t("price.title", Locale::En, &[("symbol", "ETH")]), "ETH Price");
+        assert_eq!(t("price.title", Locale::Es, &[("symbol", "ETH")]), "Precio de ETH");
+        assert_eq!(t("error.fetch", Locale::En, &[("what", "gas prices")]), "Couldn't fetch gas prices right now");
+    }
+
+    #[test]
+    fn missing_translations_fall_back_to_english_then_the_key() {
+        assert_eq!(template(&[("price.field", "Prix")], "price.field"), "Prix");
+        assert_eq!(template(&[("price.field", "Prix")], "price.title"), "{symbol} Price");
+        assert_eq!(t("no.such.key", Locale::Es, &[]), "no.such.key");
+    }
+
+    #[test]
+    fn locale_comes_from_the_language_part_of_the_tag() {
+        assert_eq!(Locale::from_tag("es-ES"), Locale::Es);
+        assert_eq!(Locale::from_tag("es"), Locale::Es);
+        assert_eq!(Locale::from_tag("en-US"), Locale::En);
+        assert_eq!(Locale::from_tag("fr"), Locale::En);
+    }
+}
@@ -1,185 +1,229 @@
-use dotenv;
+mod alerts;
+mod breaker;
+mod commands;
+mod config;
+mod format;
+mod handler;
+mod health;
+mod hooks;
+mod i18n;
+mod metrics;
+mod panics;
+mod providers;
+mod state;
+mod storage;
+mod summary;
 
-use reqwest;
-
-use std::collections::HashSet;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use serde_json::Value;
-
-use serenity::async_trait;
-use serenity::prelude::*;
-use serenity::prelude::Context;
-
-use serenity::client::bridge::gateway::{ShardId, ShardManager};
-use serenity::framework::standard::buckets::{LimitedFor, RevertBucket};
-use serenity::framework::standard::macros::{check, command, group, help, hook};
-use serenity::framework::standard::{
-    help_commands,
-    Args,
-    CommandGroup,
-    CommandOptions,
-    CommandResult,
-    DispatchError,
-    HelpOptions,
-    Reason,
-    StandardFramework,
-};
+use hyper::server::conn::AddrIncoming;
+use hyper::server::Builder;
+use serenity::framework::standard::buckets::LimitedFor;
+use serenity::framework::standard::StandardFramework;
+use serenity::gateway::GatewayError;
 use serenity::http::Http;
-use serenity::model::channel::{Channel, Message};
-use serenity::model::gateway::{GatewayIntents, Ready};
-use serenity::model::id::UserId;
-use serenity::model::permissions::Permissions;
+use serenity::model::gateway::GatewayIntents;
+use serenity::model::id::{ChannelId, GuildId, UserId};
 use serenity::prelude::*;
-use serenity::utils::{content_safe, ContentSafeOptions};
-use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
 
-struct ShardManagerContainer;
-
-impl TypeMapKey for ShardManagerContainer {
-    type Value = Arc<Mutex<ShardManager>>;
-}
-
-struct CommandCounter;
+use crate::breaker::{CircuitBreakers, Cooldowns};
+use crate::commands::alerts::poll_alerts;
+use crate::commands::meta::MY_HELP;
+use crate::commands::{disabled_commands, GROUPS};
+use crate::config::{Config, Sharding};
+use crate::handler::Handler;
+use crate::health::BotStatus;
+use crate::hooks::{after, before, delay_action, dispatch_error, dynamic_prefix, normal_message, unknown_command};
+use crate::panics::{install_hook as install_panic_hook, notify_owners};
+use crate::providers::{backoff_delay, SymbolCache, Upstream};
+use crate::state::{
+    flush_command_counter, import_legacy_json, AllowedChannels, BotOwners, BotStatusContainer, CircuitBreakerContainer,
+    CommandCounter, CommandFailureCounter, CommandTimings, ConfigContainer, FearGreedCache, GuildPrefixes,
+    GuildSettings, GuildSettingsContainer, HttpClientContainer, LiveTickers, Portfolios, PriceCache, ProviderCooldown,
+    ShardManagerContainer, StartTime, StorageContainer, SymbolCacheContainer, TopMarketsCache, TrendingCache,
+    TypingIndicators, UserPreferences,
+};
+use crate::storage::{Storage, Store};
+use crate::summary::post_daily_summaries;
 
-impl TypeMapKey for CommandCounter {
-    type Value = HashMap<String, u64>;
-}
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
-struct Handler;
+#[tokio::main]
+async fn main() {
+    let start_time = Instant::now();
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
 
-#[async_trait]
-impl EventHandler for Handler {
-    async fn ready(&self, _: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
+    let config = match Config::from_env() {
+        Ok(config) => Arc::new(config),
+        Err(why) => {
+            eprintln!("Invalid configuration:\n{}", why);
+            std::process::exit(1);
+        }
+    };
+    let health_listener = bind_or_exit("health", config.health_addr);
+    let metrics_listener = config.metrics_addr.map(|addr| (addr, bind_or_exit("metrics", addr)));
+    if config.etherscan_api_key.is_none() {
+        warn!("ETHERSCAN_API_KEY is not set; gas, balance and whois lookups are disabled and ETH prices come from CoinGecko");
     }
-}
-
-#[group]
-#[commands(eth_price, eth_balance)]
-struct General;
-
-#[help]
-#[individual_command_tip = "Hello! Use `!` as a prefix for commands\n\n\
-If you want more information about a specific command, just pass the command as argument."]
-#[command_not_found_text = "Could not find: `{}`."]
-#[max_levenshtein_distance(3)]
-#[indention_prefix = "+"]
-#[lacking_permissions = "Hide"]
-#[lacking_role = "Nothing"]
-
-async fn my_help(
-    context: &Context,
-    msg: &Message,
-    args: Args,
-    help_options: &'static HelpOptions,
-    groups: &[&'static CommandGroup],
-    owners: HashSet<UserId>,
-) -> CommandResult {
-    let _ = help_commands::with_embeds(context, msg, args, help_options, groups, owners).await;
-    Ok(())
-}
-
-#[hook]
-async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
-    println!("Got command '{}' by user '{}'", command_name, msg.author.name);
+    let http = Http::new(&config.discord_token);
 
-    // Increment the number of times this command has been run once. If
-    // the command's name does not exist in the counter, add a default
-    // value of 0.
-    let mut data = ctx.data.write().await;
-    let counter = data.get_mut::<CommandCounter>().expect("Expected CommandCounter in TypeMap.");
-    let entry = counter.entry(command_name.to_string()).or_insert(0);
-    *entry += 1;
+    let owners = match fetch_owners(&http, config.startup_max_attempts).await {
+        Ok(owners) => owners,
+        Err(why) => {
+            eprintln!("Could not access application info, check that DISCORD_TOKEN is valid: {}", why);
+            std::process::exit(1);
+        }
+    };
 
-    true // if `before` returns false, command processing doesn't happen.
-}
+    let (panic_reports, panic_receiver) = tokio::sync::mpsc::unbounded_channel();
+    install_panic_hook(panic_reports);
+    let secrets = std::iter::once(config.discord_token.clone()).chain(config.etherscan_api_key.clone()).collect();
+    tokio::spawn(notify_owners(Arc::new(http), owners.clone(), secrets, panic_receiver));
 
-#[hook]
-async fn after(_ctx: &Context, _msg: &Message, command_name: &str, command_result: CommandResult) {
-    match command_result {
-        Ok(()) => println!("Processed command '{}'", command_name),
-        Err(why) => println!("Command '{}' returned error {:?}", command_name, why),
+    let http_client = Arc::new(
+        reqwest::Client::builder()
+            .connect_timeout(HTTP_CONNECT_TIMEOUT)
+            .timeout(HTTP_REQUEST_TIMEOUT)
+            .build()
+            .expect("Err creating HTTP client"),
+    );
+    let status = Arc::new(BotStatus::new());
+    let storage = Storage::open(&config.database_path).expect("Err opening database");
+    if storage.is_new() {
+        import_legacy_json(&storage, &config).await.expect("Err importing JSON state files");
+        info!(path = %config.database_path.display(), "Created database");
     }
-}
-
-#[hook]
-async fn unknown_command(_ctx: &Context, _msg: &Message, unknown_command_name: &str) {
-    println!("Could not find command named '{}'", unknown_command_name);
-}
-
-#[hook]
-async fn normal_message(_ctx: &Context, msg: &Message) {
-    println!("Message is not a command '{}'", msg.content);
-}
-
-#[hook]
-async fn delay_action(ctx: &Context, msg: &Message) {
-    // You may want to handle a Discord rate limit if this fails.
-    let _ = msg.react(ctx, '⏱').await;
-}
-
-#[hook]
-async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError, _command_name: &str) {
-    if let DispatchError::Ratelimited(info) = error {
-        // We notify them only once.
-        if info.is_first_try {
-            let _ = msg
-                .channel_id
-                .say(&ctx.http, &format!("Try this again in {} seconds.", info.as_secs()))
-                .await;
+    if config.alerts_db_path.exists() {
+        let imported = storage.import_legacy_alerts(&config.alerts_db_path).expect("Err importing alert database");
+        if imported > 0 {
+            info!(path = %config.alerts_db_path.display(), imported, "Imported alerts");
         }
     }
-}
+    let storage: Arc<dyn Store> = Arc::new(storage);
+    let upstream = Upstream {
+        retry: config.retry,
+        breakers: Arc::new(CircuitBreakers::default()),
+        cooldowns: Arc::new(Cooldowns::default()),
+    };
+    let symbols = Arc::new(SymbolCache::load(&http_client, &upstream).await.unwrap_or_else(|why| {
+        warn!(error = %why, "Failed to load CoinGecko coin list, using well-known symbols only");
+        SymbolCache::well_known()
+    }));
+    // Once the list has been edited with `allowchannel`/`denychannel` the
+    // saved copy wins over ALLOWED_CHANNEL_IDS.
+    let allowed_channels: HashSet<ChannelId> = storage
+        .allowed_channels()
+        .expect("Err loading allowed channels")
+        .map_or_else(|| config.allowed_channels.clone(), |saved| saved.into_iter().collect())
+        .into_iter()
+        .map(ChannelId)
+        .collect();
+    let guild_prefixes: HashMap<GuildId, String> = storage
+        .guild_prefixes()
+        .expect("Err loading guild prefixes")
+        .into_iter()
+        .map(|(guild_id, prefix)| (GuildId(guild_id), prefix))
+        .collect();
+    let guild_settings: HashMap<GuildId, GuildSettings> = storage
+        .guild_embed_settings()
+        .expect("Err loading guild settings")
+        .into_iter()
+        .map(|(guild_id, use_embeds)| (GuildId(guild_id), GuildSettings { use_embeds }))
+        .collect();
+    let portfolios: HashMap<UserId, HashMap<String, f64>> = storage
+        .holdings()
+        .expect("Err loading portfolios")
+        .into_iter()
+        .map(|(user_id, holdings)| (UserId(user_id), holdings))
+        .collect();
+    let user_currencies: HashMap<UserId, String> = storage
+        .user_currencies()
+        .expect("Err loading currency preferences")
+        .into_iter()
+        .map(|(user_id, currency)| (UserId(user_id), currency))
+        .collect();
 
-use serenity::futures::future::BoxFuture;
-use serenity::FutureExt;
-fn _dispatch_error_no_macro<'fut>(
-    ctx: &'fut mut Context,
-    msg: &'fut Message,
-    error: DispatchError,
-    _command_name: &str,
-) -> BoxFuture<'fut, ()> {
-    async move {
-        if let DispatchError::Ratelimited(info) = error {
-            if info.is_first_try {
-                let _ = msg
-                    .channel_id
-                    .say(&ctx.http, &format!("Try this again in {} seconds.", info.as_secs()))
-                    .await;
-            }
-        };
+    let disabled = match &config.enabled_commands {
+        Some(enabled) => disabled_commands(GROUPS, enabled),
+        None => HashSet::new(),
+    };
+    if !disabled.is_empty() {
+        let mut names: Vec<&str> = disabled.iter().map(String::as_str).collect();
+        names.sort_unstable();
+        info!(commands = %names.join(","), "Commands disabled by ENABLED_COMMANDS");
     }
-    .boxed()
-}
 
-#[tokio::main]
-async fn main() {
-    let token = dotenv::var("DISCORD_TOKEN").unwrap();
-    let http = Http::new(&token);
-    
     let framework = StandardFramework::new()
-        .configure(|c| c.prefix("!")
-            .delimiters(vec![", ", " "])
-            .with_whitespace(true))
+        .configure(|c| c.owners(owners.clone())
+            .disabled_commands(disabled)
+            // Every prefix comes from `dynamic_prefix` so a guild's custom
+            // prefix replaces the default instead of adding to it.
+            .prefix("")
+            .dynamic_prefix(dynamic_prefix)
+            .delimiters(config.arg_delimiters.clone())
+            .with_whitespace(config.arg_with_whitespace))
                 .before(before)
                 .after(after)
                 .unrecognised_command(unknown_command)
                 .normal_message(normal_message)
-                .bucket("emoji", |b| b.delay(5)).await
-                .bucket("complicated", |b| b.limit(2).time_span(30).delay(5)
+                .on_dispatch_error(dispatch_error)
+                .bucket("emoji", |b| b.delay(config.emoji_bucket.delay)
+                    .limit(config.emoji_bucket.limit)
+                    .time_span(config.emoji_bucket.time_span)).await
+                .bucket("complicated", |b| b.delay(config.complicated_bucket.delay)
+                    .limit(config.complicated_bucket.limit)
+                    .time_span(config.complicated_bucket.time_span)
                     .limit_for(LimitedFor::Channel)
                     .await_ratelimits(1)
                     .delay_action(delay_action)).await
-                .help(&MY_HELP)
-                .group(&GENERAL_GROUP);
+                .bucket("price", |b| b.delay(config.price_bucket.delay)
+                    .limit(config.price_bucket.limit)
+                    .time_span(config.price_bucket.time_span)
+                    .limit_for(LimitedFor::User)
+                    .await_ratelimits(1)
+                    .delay_action(delay_action)).await
+                .bucket("api", |b| b.delay(config.api_bucket.delay)
+                    .limit(config.api_bucket.limit)
+                    .time_span(config.api_bucket.time_span)
+                    .limit_for(LimitedFor::User)).await
+                .help(&MY_HELP);
+        let framework = GROUPS.iter().fold(framework, |framework, group| framework.group(group));
 
         let intents = GatewayIntents::all();
-        let mut client = Client::builder(&token, intents)
+        let mut client = Client::builder(&config.discord_token, intents)
             .event_handler(Handler)
             .framework(framework)
-            .type_map_insert::<CommandCounter>(HashMap::default())
+            .type_map_insert::<CommandCounter>(storage.command_counts().expect("Err loading command counts"))
+            .type_map_insert::<CommandFailureCounter>(HashMap::default())
+            .type_map_insert::<CommandTimings>(HashMap::default())
+            .type_map_insert::<TypingIndicators>(HashMap::default())
+            .type_map_insert::<GuildPrefixes>(guild_prefixes)
+            .type_map_insert::<GuildSettingsContainer>(guild_settings)
+            .type_map_insert::<AllowedChannels>(allowed_channels)
+            .type_map_insert::<LiveTickers>(HashMap::new())
+            .type_map_insert::<Portfolios>(portfolios)
+            .type_map_insert::<UserPreferences>(user_currencies)
+            .type_map_insert::<ConfigContainer>(Arc::clone(&config))
+            .type_map_insert::<BotStatusContainer>(Arc::clone(&status))
+            .type_map_insert::<StartTime>(start_time)
+            .type_map_insert::<BotOwners>(owners)
+            .type_map_insert::<HttpClientContainer>(Arc::clone(&http_client))
+            .type_map_insert::<CircuitBreakerContainer>(Arc::clone(&upstream.breakers))
+            .type_map_insert::<ProviderCooldown>(Arc::clone(&upstream.cooldowns))
+            .type_map_insert::<StorageContainer>(Arc::clone(&storage))
+            .type_map_insert::<PriceCache>(Arc::new(Mutex::new(HashMap::default())))
+            .type_map_insert::<TrendingCache>(Arc::new(Mutex::new(None)))
+            .type_map_insert::<TopMarketsCache>(Arc::new(Mutex::new(None)))
+            .type_map_insert::<FearGreedCache>(Arc::new(Mutex::new(None)))
+            .type_map_insert::<SymbolCacheContainer>(Arc::clone(&symbols))
             .await
             .expect("Err creating client");
     
@@ -188,71 +232,180 @@ async fn main() {
             data.insert::<ShardManagerContainer>(Arc::clone(&client.shard_manager));
         }
     
-        if let Err(why) = client.start().await {
-            println!("Client error: {:?}", why);
+        {
+            let data = Arc::clone(&client.data);
+            let config = Arc::clone(&config);
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(config.command_counter_flush_interval);
+                loop {
+                    interval.tick().await;
+                    flush_command_counter(&data).await;
+                }
+            });
+        }
+
+        tokio::spawn(poll_alerts(
+            Arc::clone(&client.cache_and_http.http),
+            storage,
+            Arc::clone(&http_client),
+            upstream.clone(),
+            Arc::clone(&config),
+        ));
+        tokio::spawn(post_daily_summaries(
+            Arc::clone(&client.cache_and_http.http),
+            http_client,
+            upstream,
+            symbols,
+            Arc::clone(&config),
+        ));
+
+        {
+            let shard_manager = Arc::clone(&client.shard_manager);
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                info!("Shutdown signal received, disconnecting shards");
+                shard_manager.lock().await.shutdown_all().await;
+            });
+        }
+
+        let (stop_health, health_stopped) = tokio::sync::oneshot::channel::<()>();
+        info!(addr = %config.health_addr, "Health endpoint listening");
+        let health = tokio::spawn(health::serve(health_listener, Arc::clone(&status), async {
+            let _ = health_stopped.await;
+        }));
+
+        let (stop_metrics, metrics_stopped) = tokio::sync::oneshot::channel::<()>();
+        let metrics_server = metrics_listener.map(|(addr, listener)| {
+            info!(addr = %addr, "Metrics endpoint listening");
+            tokio::spawn(health::serve_metrics(listener, async {
+                let _ = metrics_stopped.await;
+            }))
+        });
+
+        let mut attempt = 0;
+        loop {
+            let started = match config.sharding {
+                Sharding::Single => client.start().await,
+                Sharding::Fixed(count) => client.start_shards(count).await,
+                Sharding::Auto => client.start_autosharded().await,
+            };
+            let why = match started {
+                Ok(()) => break,
+                Err(why) => why,
+            };
+            if is_fatal_client_error(&why) {
+                error!(error = ?why, "Client error, not retrying");
+                break;
+            }
+            attempt += 1;
+            if attempt >= config.startup_max_attempts {
+                error!(error = ?why, attempts = attempt, "Client error, giving up");
+                break;
+            }
+            let delay = backoff_delay(STARTUP_RETRY_BASE_DELAY, attempt - 1);
+            warn!(error = ?why, attempt, delay_ms = delay.as_millis() as u64, "Client error, retrying");
+            tokio::time::sleep(delay).await;
+        }
+
+        status.set_connected(false);
+        let _ = stop_health.send(());
+        match health.await {
+            Ok(Err(why)) => error!(error = %why, "Health endpoint failed"),
+            Err(why) => error!(error = %why, "Health endpoint task panicked"),
+            Ok(Ok(())) => {},
+        }
+        let _ = stop_metrics.send(());
+        if let Some(metrics_server) = metrics_server {
+            match metrics_server.await {
+                Ok(Err(why)) => error!(error = %why, "Metrics endpoint failed"),
+                Err(why) => error!(error = %why, "Metrics endpoint task panicked"),
+                Ok(Ok(())) => {},
+            }
         }
+
+        flush_command_counter(&client.data).await;
+        info!("Shutdown complete");
 }
 
-#[command]
-async fn eth_price(ctx: &Context, msg: &Message) -> CommandResult {
-    let etherscan_api_key = dotenv::var("ETHERSCAN_API_KEY").unwrap();
-    let client = reqwest::Client::new();
-    let response = client.get(format!("https://api.etherscan.io/api?module=stats&action=ethprice&apikey={}", etherscan_api_key))
-        .send()
-        .await
-        .unwrap();
-    match response.status() {
-        reqwest::StatusCode::OK => {
-            let body = response.text().await.unwrap();
-            let json: Value = serde_json::from_str(&body).unwrap();
-            let price = json["result"]["ethusd"].as_str().unwrap();
-            msg.reply(&ctx.http, format!("The current price of ETH is ${}", price)).await?;
-        },
-        _ => {
-            msg.reply(&ctx.http, "Something went wrong").await?;
+/// Binds the `name` endpoint to `addr`, exiting if that fails.
+fn bind_or_exit(name: &str, addr: SocketAddr) -> Builder<AddrIncoming> {
+    match health::bind(addr) {
+        Ok(listener) => listener,
+        Err(why) => {
+            eprintln!("Could not bind the {} endpoint to {}: {}", name, addr, why);
+            std::process::exit(1);
         }
     }
-    Ok(())
 }
 
-#[command]
-async fn eth_balance(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    match args.single_quoted::<String>() {
-        Ok(account) => {
-            let settings = if let Some(guild_id) = msg.guild_id {
-                ContentSafeOptions::default()
-                    .clean_channel(false)
-                    .display_as_member_from(guild_id)
-            } else {
-                ContentSafeOptions::default().clean_channel(false).clean_role(false)
-            };
-            
-            let etherscan_api_key = dotenv::var("ETHERSCAN_API_KEY").unwrap();
-            let client = reqwest::Client::new();
-            let response = client.get(format!("https://api.etherscan.io/api?module=account\
-                                               &action=balance&address={}&tag=latest&apikey={}", account, etherscan_api_key))
-                .send()
-                .await
-                .unwrap();
-            match response.status() {
-                reqwest::StatusCode::OK => {
-                    let body = response.text().await.unwrap();
-                    let json: Value = serde_json::from_str(&body).unwrap();
-                    let balance = format!("{:.2}", (json["result"].as_str().unwrap().parse::<f64>().unwrap() / 1000000000000000000_f64));
-                    let reply = format!("The balance of {} is {} ETH", account, balance);
-                    let content = content_safe(&ctx.cache, reply, &settings, &msg.mentions);
-                    msg.channel_id.say(&ctx.http, &content).await?;
-                    return Ok(());
-                },
-                _ => {
-                    msg.reply(&ctx.http, "Something went wrong").await?;
-                    return Ok(());
+/// Resolves on Ctrl-C, or on SIGTERM where that exists.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("Err installing SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = terminate.recv() => {},
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// The application's owners, retrying outages and rate limits the way the
+/// client start-up loop does.
+async fn fetch_owners(http: &Http, max_attempts: u32) -> serenity::Result<HashSet<UserId>> {
+    let mut attempt = 0;
+    loop {
+        let why = match http.get_current_application_info().await {
+            Ok(info) => {
+                let mut owners = HashSet::new();
+                if let Some(team) = info.team {
+                    owners.insert(team.owner_user_id);
+                } else {
+                    owners.insert(info.owner.id);
                 }
-            }
-        },
-        Err(_) => {
-            msg.reply(ctx, "An argument is required to run this command.").await?;
-            return Ok(());
-        },
-    };
+                return Ok(owners);
+            },
+            Err(why) => why,
+        };
+        attempt += 1;
+        if is_fatal_http_error(&why) || attempt >= max_attempts {
+            return Err(why);
+        }
+        let delay = backoff_delay(STARTUP_RETRY_BASE_DELAY, attempt - 1);
+        warn!(error = %why, attempt, delay_ms = delay.as_millis() as u64, "Could not access application info, retrying");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Requests Discord rejected outright, such as with an invalid token, rather
+/// than rate limited or failed to answer.
+fn is_fatal_http_error(why: &serenity::Error) -> bool {
+    match why {
+        serenity::Error::Http(why) => why
+            .status_code()
+            .map_or(false, |status| status.is_client_error() && status.as_u16() != 429),
+        _ => false,
+    }
+}
+
+/// First wait before restarting a client that failed; doubles per attempt.
+const STARTUP_RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+/// Errors a restart can't fix, such as a rejected token.
+fn is_fatal_client_error(why: &serenity::Error) -> bool {
+    matches!(
+        why,
+        serenity::Error::Gateway(
+            GatewayError::InvalidAuthentication
+                | GatewayError::InvalidGatewayIntents
+                | GatewayError::DisallowedGatewayIntents
+        )
+    )
 }
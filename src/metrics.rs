@@ -0,0 +1,95 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+
+/// Prometheus series served from `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    commands: IntCounter,
+    command_invocations: IntCounterVec,
+    command_errors: IntCounterVec,
+    command_duration: HistogramVec,
+    api_request_duration: HistogramVec,
+    api_errors: IntCounterVec,
+}
+
+/// The process-wide metrics, registered on first use.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let registry = Registry::new();
+        let commands = IntCounter::new("bot_commands_total", "Commands dispatched").expect("valid metric");
+        let command_invocations = IntCounterVec::new(
+            Opts::new("bot_command_invocations_total", "Commands dispatched, by command"),
+            &["command"],
+        ).expect("valid metric");
+        let command_errors = IntCounterVec::new(
+            Opts::new("bot_command_errors_total", "Commands that returned an error, by command"),
+            &["command"],
+        ).expect("valid metric");
+        let command_duration = HistogramVec::new(
+            HistogramOpts::new("bot_command_duration_seconds", "Time from dispatch to completion, by command"),
+            &["command"],
+        ).expect("valid metric");
+        let api_request_duration = HistogramVec::new(
+            HistogramOpts::new("bot_api_request_duration_seconds", "Upstream API request time, including retries"),
+            &["api"],
+        ).expect("valid metric");
+        let api_errors = IntCounterVec::new(
+            Opts::new("bot_api_errors_total", "Upstream API requests that failed"),
+            &["api"],
+        ).expect("valid metric");
+
+        registry.register(Box::new(commands.clone())).expect("metric registered once");
+        registry.register(Box::new(command_invocations.clone())).expect("metric registered once");
+        registry.register(Box::new(command_errors.clone())).expect("metric registered once");
+        registry.register(Box::new(command_duration.clone())).expect("metric registered once");
+        registry.register(Box::new(api_request_duration.clone())).expect("metric registered once");
+        registry.register(Box::new(api_errors.clone())).expect("metric registered once");
+
+        Metrics {
+            registry,
+            commands,
+            command_invocations,
+            command_errors,
+            command_duration,
+            api_request_duration,
+            api_errors,
+        }
+    }
+
+    pub fn record_command(&self, command: &str) {
+        self.commands.inc();
+        self.command_invocations.with_label_values(&[command]).inc();
+    }
+
+    /// Records how a command finished; `elapsed` is `None` when its start wasn't seen.
+    pub fn record_command_result(&self, command: &str, elapsed: Option<Duration>, succeeded: bool) {
+        if let Some(elapsed) = elapsed {
+            self.command_duration.with_label_values(&[command]).observe(elapsed.as_secs_f64());
+        }
+        if !succeeded {
+            self.command_errors.with_label_values(&[command]).inc();
+        }
+    }
+
+    pub fn record_api_request(&self, api: &str, elapsed: Duration, succeeded: bool) {
+        self.api_request_duration.with_label_values(&[api]).observe(elapsed.as_secs_f64());
+        if !succeeded {
+            self.api_errors.with_label_values(&[api]).inc();
+        }
+    }
+
+    /// Renders every series in the Prometheus text exposition format.
+    pub fn encode(&self) -> Result<(String, Vec<u8>), prometheus::Error> {
+        let encoder = TextEncoder::new();
+        let mut buffer = Vec::new();
+        encoder.encode(&self.registry.gather(), &mut buffer)?;
+        Ok((encoder.format_type().to_string(), buffer))
+    }
+}
@@ -0,0 +1,107 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::panic::Location;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serenity::http::Http;
+use serenity::model::id::UserId;
+use tokio::sync::mpsc;
+use tokio::task;
+use tracing::warn;
+
+/// Longest panic summary sent to owners.
+const MAX_SUMMARY_CHARS: usize = 500;
+
+/// A panic, with the command that was running on the panicking task if known.
+pub struct PanicReport {
+    pub command: Option<String>,
+    pub summary: String,
+}
+
+/// Undoes whatever `before` set up for a command that panicked.
+type Cleanup = Box<dyn FnOnce() + Send>;
+
+/// The command each dispatch task is running, so a panic can be traced back to it.
+fn running() -> &'static Mutex<HashMap<task::Id, (String, Cleanup)>> {
+    static RUNNING: OnceLock<Mutex<HashMap<task::Id, (String, Cleanup)>>> = OnceLock::new();
+    RUNNING.get_or_init(Default::default)
+}
+
+/// Notes that the current task is running `command`, and what to clean up
+/// if it panics. Called from `before`.
+pub fn command_started(command: &str, on_panic: impl FnOnce() + Send + 'static) {
+    if let Some(id) = task::try_id() {
+        running()
+            .lock()
+            .expect("running commands poisoned")
+            .insert(id, (command.to_string(), Box::new(on_panic)));
+    }
+}
+
+/// Forgets the current task's command without running its cleanup. Called
+/// from `after`, which a panicking command never reaches.
+pub fn command_finished() {
+    if let Some(id) = task::try_id() {
+        running().lock().expect("running commands poisoned").remove(&id);
+    }
+}
+
+fn panic_summary(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload");
+    match location {
+        Some(location) => format!("{} at {}:{}", message, location.file(), location.line()),
+        None => message.to_string(),
+    }
+}
+
+/// Keeps the default panic output and also sends every panic to `reports`.
+pub fn install_hook(reports: mpsc::UnboundedSender<PanicReport>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        // `try_lock` so a panic can never deadlock on the map it reads.
+        let (command, on_panic) = task::try_id()
+            .and_then(|id| running().try_lock().ok()?.remove(&id))
+            .unzip();
+        let _ = reports.send(PanicReport {
+            command,
+            summary: panic_summary(info.payload(), info.location()),
+        });
+        if let Some(on_panic) = on_panic {
+            on_panic();
+        }
+    }));
+}
+
+/// DMs every owner about each reported panic, with `secrets` redacted from
+/// the summary. Runs until the panic hook's sender is gone.
+pub async fn notify_owners(
+    http: Arc<Http>,
+    owners: HashSet<UserId>,
+    secrets: Vec<String>,
+    mut reports: mpsc::UnboundedReceiver<PanicReport>,
+) {
+    while let Some(report) = reports.recv().await {
+        let summary = secrets
+            .iter()
+            .fold(report.summary, |summary, secret| summary.replace(secret.as_str(), "[redacted]"));
+        let summary: String = summary.replace("```", "'''").chars().take(MAX_SUMMARY_CHARS).collect();
+        let content = match &report.command {
+            Some(command) => format!("Command `{}` panicked:\n```\n{}\n```", command, summary),
+            None => format!("A background task panicked:\n```\n{}\n```", summary),
+        };
+        for owner in &owners {
+            let sent = match owner.create_dm_channel(&*http).await {
+                Ok(channel) => channel.say(&http, &content).await.map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                warn!(user_id = %owner, error = %why, "Couldn't DM owner about a panic");
+            }
+        }
+    }
+}
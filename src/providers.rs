@@ -0,0 +1,952 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::Value;
+use serenity::async_trait;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::breaker::{CircuitBreakers, Cooldowns};
+use crate::commands::normalize_symbol;
+use crate::config::{Config, RetryPolicy};
+use crate::i18n::{t, Locale};
+use crate::metrics::metrics;
+
+/// Lowercase ticker symbols mapped to CoinGecko coin ids.
+pub struct SymbolCache {
+    ids: HashMap<String, String>,
+}
+
+/// Symbols shared by many coins on CoinGecko resolve to the coin people usually mean.
+const WELL_KNOWN_SYMBOLS: &[(&str, &str)] = &[
+    ("btc", "bitcoin"),
+    ("eth", "ethereum"),
+    ("usdt", "tether"),
+    ("usdc", "usd-coin"),
+    ("dai", "dai"),
+    ("link", "chainlink"),
+    ("uni", "uniswap"),
+    ("sol", "solana"),
+    ("matic", "matic-network"),
+    ("doge", "dogecoin"),
+];
+
+impl SymbolCache {
+    pub fn well_known() -> Self {
+        let ids = WELL_KNOWN_SYMBOLS
+            .iter()
+            .map(|(symbol, id)| (symbol.to_string(), id.to_string()))
+            .collect();
+        SymbolCache { ids }
+    }
+
+    /// Loads the full CoinGecko coin list on top of the well-known symbols.
+    pub async fn load(client: &reqwest::Client, upstream: &Upstream) -> Result<Self, PriceError> {
+        let url = "https://api.coingecko.com/api/v3/coins/list";
+        let coins: Vec<Value> = fetch_with_retry(client, url, upstream, |body| Ok(serde_json::from_str(body)?)).await?;
+
+        let mut cache = SymbolCache::well_known();
+        for coin in &coins {
+            if let (Some(symbol), Some(id)) = (coin["symbol"].as_str(), coin["id"].as_str()) {
+                cache.ids.entry(normalize_symbol(symbol)).or_insert_with(|| id.to_string());
+            }
+        }
+        Ok(cache)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&str> {
+        self.ids.get(symbol).map(String::as_str)
+    }
+}
+
+/// The CoinGecko id for a user-typed `symbol`, or `None` when it isn't a
+/// known coin, so callers can reject it without an upstream request.
+pub fn resolve_symbol(cache: &SymbolCache, symbol: &str) -> Option<String> {
+    let symbol = normalize_symbol(symbol);
+    if symbol.is_empty() {
+        return None;
+    }
+    cache.get(&symbol).map(str::to_string)
+}
+
+#[derive(Debug)]
+pub enum PriceError {
+    Http(reqwest::Error),
+    Parse(serde_json::Error),
+    MissingField(&'static str),
+    Busy,
+    TimedOut,
+    /// The API answered but reported an error of its own.
+    Upstream(String),
+    /// Not sent because the API's circuit breaker is open.
+    Unavailable,
+    /// Still answered 429 after retrying, or not sent during the cooldown that follows.
+    RateLimited,
+    /// The provider doesn't quote prices in this currency.
+    UnsupportedCurrency(String),
+}
+
+impl PriceError {
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            PriceError::Http(why) => why.is_timeout(),
+            PriceError::TimedOut => true,
+            _ => false,
+        }
+    }
+
+    /// What to tell the user when looking up `what` failed.
+    pub fn user_message(&self, what: &str) -> String {
+        self.localized_message(what, Locale::En)
+    }
+
+    /// `user_message` in `locale`, where `what` is already translated.
+    pub fn localized_message(&self, what: &str, locale: Locale) -> String {
+        let key = if self.is_timeout() {
+            "error.timeout"
+        } else {
+            match self {
+                PriceError::Busy => "error.busy",
+                PriceError::RateLimited => "error.rate_limited",
+                PriceError::Unavailable => "error.unavailable",
+                _ => "error.fetch",
+            }
+        };
+        t(key, locale, &[("what", what)])
+    }
+}
+
+impl fmt::Display for PriceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceError::Http(why) => write!(f, "HTTP request failed: {}", why),
+            PriceError::Parse(why) => write!(f, "invalid JSON in response: {}", why),
+            PriceError::MissingField(field) => write!(f, "response is missing field `{}`", field),
+            PriceError::Busy => f.write_str("service stayed busy after retrying"),
+            PriceError::TimedOut => f.write_str("no response before the deadline"),
+            PriceError::Upstream(reason) => write!(f, "API reported an error: {}", reason),
+            PriceError::Unavailable => f.write_str("circuit breaker open after repeated failures"),
+            PriceError::RateLimited => f.write_str("rate limited by the API"),
+            PriceError::UnsupportedCurrency(currency) => write!(f, "no prices quoted in {}", currency),
+        }
+    }
+}
+
+impl std::error::Error for PriceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PriceError::Http(why) => Some(why),
+            PriceError::Parse(why) => Some(why),
+            PriceError::MissingField(_)
+            | PriceError::Busy
+            | PriceError::TimedOut
+            | PriceError::Upstream(_)
+            | PriceError::Unavailable
+            | PriceError::RateLimited
+            | PriceError::UnsupportedCurrency(_) => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for PriceError {
+    /// Drops the URL, which for Etherscan carries the API key, so logging the error can't leak it.
+    fn from(why: reqwest::Error) -> Self {
+        PriceError::Http(why.without_url())
+    }
+}
+
+impl From<serde_json::Error> for PriceError {
+    fn from(why: serde_json::Error) -> Self {
+        PriceError::Parse(why)
+    }
+}
+
+/// The `result` of an Etherscan response body.
+pub fn etherscan_result(body: &str) -> Result<Value, PriceError> {
+    let mut json: Value = serde_json::from_str(body)?;
+    // Etherscan reports failures, including its rate limit, as a 200 with
+    // `status: "0"` and an explanation in `result`/`message`.
+    if json["status"].as_str() == Some("0") {
+        let reason = json["result"].as_str().or(json["message"].as_str()).unwrap_or("unknown error");
+        if reason.to_lowercase().contains("rate limit") {
+            return Err(PriceError::Busy);
+        }
+        return Err(PriceError::Upstream(reason.to_string()));
+    }
+    Ok(json["result"].take())
+}
+
+/// Extracts `field` (`ethusd` or `ethbtc`) from an Etherscan `stats/ethprice` response body.
+fn parse_eth_price(body: &str, field: &'static str) -> Result<f64, PriceError> {
+    etherscan_result(body)?[field]
+        .as_str()
+        .and_then(|price| price.parse::<f64>().ok())
+        .ok_or(PriceError::MissingField(field))
+}
+
+/// Deadline for a single upstream request, enforced even if the client's
+/// own timeout is missing or longer.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(8);
+
+/// No command should spend longer than this on one fetch, requests and
+/// retries included.
+const RETRY_BUDGET: Duration = Duration::from_secs(15);
+
+/// How long to leave an API alone after it rate-limits us, unless it sends `Retry-After`.
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// The retry policy and the state shared by every request to an upstream API.
+/// Cloning is cheap; the state lives in the TypeMap.
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    pub retry: RetryPolicy,
+    pub breakers: Arc<CircuitBreakers>,
+    pub cooldowns: Arc<Cooldowns>,
+}
+
+/// GETs `url` and reads the body with `parse`, retrying 429 and 5xx responses
+/// with jittered exponential backoff (or the server's `Retry-After`) until
+/// the policy's retries or the retry budget run out. The whole exchange is
+/// timed per API host, and refused outright while that host is cooling down
+/// from a rate limit or its circuit breaker is open.
+pub async fn fetch_with_retry<T>(
+    client: &reqwest::Client,
+    url: &str,
+    upstream: &Upstream,
+    parse: impl FnOnce(&str) -> Result<T, PriceError>,
+) -> Result<T, PriceError> {
+    let api = reqwest::Url::parse(url).ok().and_then(|url| url.host_str().map(str::to_string)).unwrap_or_default();
+    if let Some(remaining) = upstream.cooldowns.remaining(&api) {
+        info!(api = %api, remaining_secs = remaining.as_secs(), "Skipping request during rate-limit cooldown");
+        return Err(PriceError::RateLimited);
+    }
+    if !upstream.breakers.allow_request(&api, upstream.retry) {
+        return Err(PriceError::Unavailable);
+    }
+
+    let started = Instant::now();
+    let fetched = fetch_with_retry_inner(client, url, &api, upstream, started).await;
+    let answered = fetched.is_ok();
+    let result = fetched.and_then(|body| parse(&body));
+    metrics().record_api_request(&api, started.elapsed(), result.is_ok());
+    // An API that answers that it's busy is up; any other error, including a
+    // body that doesn't parse, counts towards opening its breaker.
+    let failed = match &result {
+        Ok(_) => false,
+        Err(PriceError::Busy) => !answered,
+        Err(_) => true,
+    };
+    upstream.breakers.record_request(&api, upstream.retry, failed);
+    result
+}
+
+async fn fetch_with_retry_inner(
+    client: &reqwest::Client,
+    url: &str,
+    api: &str,
+    upstream: &Upstream,
+    started: Instant,
+) -> Result<String, PriceError> {
+    let retry = upstream.retry;
+    let mut attempt = 0;
+    loop {
+        let response = with_deadline(step_deadline(started), client.get(url).send()).await?;
+        let status = response.status();
+        let rate_limited = status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        if !rate_limited && !status.is_server_error() {
+            return with_deadline(step_deadline(started), response.error_for_status()?.text()).await;
+        }
+
+        let delay = retry_after(&response).unwrap_or_else(|| backoff_delay(retry.base_delay, attempt));
+        if attempt >= retry.max_retries || started.elapsed() + delay > RETRY_BUDGET {
+            if rate_limited {
+                upstream.cooldowns.start(api, retry_after(&response).unwrap_or(RATE_LIMIT_COOLDOWN));
+                return Err(PriceError::RateLimited);
+            }
+            return Err(PriceError::Busy);
+        }
+        warn!(status = %status, attempt, delay_ms = delay.as_millis() as u64, "Retrying upstream request");
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// How long the next step of a fetch started at `started` may take: the
+/// request deadline, cut short by whatever is left of the retry budget.
+fn step_deadline(started: Instant) -> Duration {
+    REQUEST_DEADLINE.min(RETRY_BUDGET.saturating_sub(started.elapsed()))
+}
+
+async fn with_deadline<T>(
+    deadline: Duration,
+    request: impl std::future::Future<Output = reqwest::Result<T>>,
+) -> Result<T, PriceError> {
+    match tokio::time::timeout(deadline, request).await {
+        Ok(result) => Ok(result?),
+        Err(_) => Err(PriceError::TimedOut),
+    }
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let secs = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    secs.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+pub fn backoff_delay(base_delay: Duration, attempt: u32) -> Duration {
+    let base = base_delay * 2u32.saturating_pow(attempt);
+    let jitter = rand::thread_rng().gen_range(0..=base.as_millis() as u64 / 2);
+    base + Duration::from_millis(jitter)
+}
+
+pub const ETHERSCAN_API_URL: &str = "https://api.etherscan.io/api";
+
+/// Fetches the ETH/USD price from the Etherscan-compatible API at `base_url`.
+async fn fetch_eth_price(client: &reqwest::Client, base_url: &str, api_key: &str, upstream: &Upstream) -> Result<f64, PriceError> {
+    fetch_etherscan_quote(client, base_url, api_key, "ethusd", upstream).await
+}
+
+async fn fetch_etherscan_quote(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    field: &'static str,
+    upstream: &Upstream,
+) -> Result<f64, PriceError> {
+    let url = format!("{}?module=stats&action=ethprice&apikey={}", base_url, api_key);
+    fetch_with_retry(client, &url, upstream, |body| parse_eth_price(body, field)).await
+}
+
+const WEI_PER_ETH: f64 = 1e18;
+
+/// Whether `input` is a `0x`-prefixed, 40 hex digit Ethereum address.
+pub fn is_eth_address(input: &str) -> bool {
+    input.len() == 42 && input.starts_with("0x") && input[2..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The ETH balance of `address`, which should already have passed `is_eth_address`.
+pub async fn fetch_eth_balance(client: &reqwest::Client, api_key: &str, address: &str, upstream: &Upstream) -> Result<f64, PriceError> {
+    let url = format!(
+        "{}?module=account&action=balance&address={}&tag=latest&apikey={}",
+        ETHERSCAN_API_URL, address, api_key
+    );
+    fetch_with_retry(client, &url, upstream, |body| {
+        // Balances are decimal strings in wei and can exceed `u64`.
+        etherscan_result(body)?
+            .as_str()
+            .and_then(|wei| wei.parse::<u128>().ok())
+            .map(|wei| wei as f64 / WEI_PER_ETH)
+            .ok_or(PriceError::MissingField("result"))
+    })
+    .await
+}
+
+/// Currencies `eth_price` can quote in. USD and BTC come from Etherscan when
+/// it's configured; everything else comes from CoinGecko.
+pub fn supported_currencies() -> &'static [&'static str] {
+    &["usd", "eur", "gbp", "jpy", "cad", "aud", "chf", "btc"]
+}
+
+pub async fn fetch_coingecko_price(client: &reqwest::Client, coin_id: &str, currency: &str, upstream: &Upstream) -> Result<f64, PriceError> {
+    let url = format!("https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}", coin_id, currency);
+    fetch_with_retry(client, &url, upstream, |body| {
+        let json: Value = serde_json::from_str(body)?;
+        json[coin_id][currency].as_f64().ok_or(PriceError::MissingField("price"))
+    })
+    .await
+}
+
+/// Returns the cached price for `key` while it is younger than `ttl`, otherwise
+/// calls `fetch` and stores the result. Failed fetches are not cached.
+pub async fn cached_price<F, Fut>(
+    cache: &Mutex<HashMap<String, (f64, Instant)>>,
+    key: &str,
+    ttl: Duration,
+    fetch: F,
+) -> Result<(f64, Instant), PriceError>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<f64, PriceError>>,
+{
+    if let Some((price, fetched_at)) = cache.lock().await.get(key).copied() {
+        if fetched_at.elapsed() < ttl {
+            return Ok((price, fetched_at));
+        }
+    }
+
+    let price = fetch().await?;
+    let fetched_at = Instant::now();
+    cache.lock().await.insert(key.to_string(), (price, fetched_at));
+    Ok((price, fetched_at))
+}
+
+/// A source of ETH price quotes.
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn eth_price(&self, currency: &str) -> Result<f64, PriceError>;
+}
+
+/// Only quotes USD and BTC; callers fall back to another provider for other currencies.
+pub struct Etherscan {
+    pub client: Arc<reqwest::Client>,
+    pub base_url: String,
+    pub api_key: String,
+    pub upstream: Upstream,
+}
+
+#[async_trait]
+impl PriceProvider for Etherscan {
+    fn name(&self) -> &'static str {
+        "etherscan"
+    }
+
+    async fn eth_price(&self, currency: &str) -> Result<f64, PriceError> {
+        match currency {
+            "usd" => fetch_eth_price(&self.client, &self.base_url, &self.api_key, &self.upstream).await,
+            "btc" => fetch_etherscan_quote(&self.client, &self.base_url, &self.api_key, "ethbtc", &self.upstream).await,
+            _ => Err(PriceError::UnsupportedCurrency(currency.to_string())),
+        }
+    }
+}
+
+pub struct CoinGecko {
+    pub client: Arc<reqwest::Client>,
+    pub upstream: Upstream,
+}
+
+#[async_trait]
+impl PriceProvider for CoinGecko {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
+    async fn eth_price(&self, currency: &str) -> Result<f64, PriceError> {
+        fetch_coingecko_price(&self.client, "ethereum", currency, &self.upstream).await
+    }
+}
+
+/// Asks each provider in turn, returning the first price quoted. The client
+/// timeout bounds each attempt, so a hung provider also falls through.
+pub struct FallbackProvider<'a> {
+    pub providers: Vec<&'a dyn PriceProvider>,
+}
+
+#[async_trait]
+impl PriceProvider for FallbackProvider<'_> {
+    fn name(&self) -> &'static str {
+        "fallback"
+    }
+
+    async fn eth_price(&self, currency: &str) -> Result<f64, PriceError> {
+        let mut last_error = None;
+        for provider in &self.providers {
+            match provider.eth_price(currency).await {
+                Ok(price) => {
+                    info!(provider = provider.name(), "Fetched ETH price");
+                    return Ok(price);
+                },
+                // Not a failure: this provider just can't help with `currency`.
+                Err(why @ PriceError::UnsupportedCurrency(_)) => {
+                    debug!(provider = provider.name(), currency, "Skipping ETH price provider");
+                    last_error.get_or_insert(why);
+                },
+                Err(why) => {
+                    warn!(provider = provider.name(), error = %why, "ETH price provider failed");
+                    last_error = Some(why);
+                }
+            }
+        }
+        Err(last_error.expect("FallbackProvider has no providers"))
+    }
+}
+
+/// Where an ETH price quote comes from.
+#[derive(Debug, PartialEq)]
+pub enum EthPriceSource<'a> {
+    Etherscan { api_key: &'a str },
+    CoinGecko,
+}
+
+/// Etherscan only quotes USD and BTC and needs an API key; everything else goes to the keyless CoinGecko API.
+pub fn eth_price_source<'a>(config: &'a Config, currency: &str) -> EthPriceSource<'a> {
+    match &config.etherscan_api_key {
+        Some(api_key) if currency == "usd" || currency == "btc" => EthPriceSource::Etherscan { api_key },
+        _ => EthPriceSource::CoinGecko,
+    }
+}
+
+/// Most ids `fetch_prices` puts in one URL, keeping it well under common URL length limits.
+const PRICE_BATCH_SIZE: usize = 100;
+
+/// Prices several CoinGecko coins in `currency`, one request per
+/// `PRICE_BATCH_SIZE` ids. Coins CoinGecko doesn't price are left out.
+pub async fn fetch_prices(client: &reqwest::Client, ids: &[String], currency: &str, upstream: &Upstream) -> Result<HashMap<String, f64>, PriceError> {
+    let mut prices = HashMap::with_capacity(ids.len());
+    for batch in ids.chunks(PRICE_BATCH_SIZE) {
+        let url = format!(
+            "https://api.coingecko.com/api/v3/simple/price?ids={}&vs_currencies={}",
+            batch.join(","),
+            currency
+        );
+        let json: Value = fetch_with_retry(client, &url, upstream, |body| Ok(serde_json::from_str(body)?)).await?;
+        prices.extend(batch.iter().filter_map(|id| json[id][currency].as_f64().map(|price| (id.clone(), price))));
+    }
+    Ok(prices)
+}
+
+/// Price history for a coin in USD, oldest first.
+pub async fn fetch_market_chart(client: &reqwest::Client, coin_id: &str, days: u32, upstream: &Upstream) -> Result<Vec<f64>, PriceError> {
+    let url = format!("https://api.coingecko.com/api/v3/coins/{}/market_chart?vs_currency=usd&days={}", coin_id, days);
+    fetch_with_retry(client, &url, upstream, |body| {
+        let json: Value = serde_json::from_str(body)?;
+        let points = json["prices"].as_array().ok_or(PriceError::MissingField("prices"))?;
+        Ok(points.iter().filter_map(|point| point[1].as_f64()).collect())
+    })
+    .await
+}
+
+/// One coin from CoinGecko's `/coins/markets`, priced in USD.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketData {
+    pub id: String,
+    pub symbol: String,
+    pub name: String,
+    pub current_price: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub market_cap_rank: Option<u32>,
+    pub total_volume: Option<f64>,
+    pub high_24h: Option<f64>,
+    pub low_24h: Option<f64>,
+    pub price_change_percentage_24h: Option<f64>,
+    pub circulating_supply: Option<f64>,
+    pub total_supply: Option<f64>,
+    pub max_supply: Option<f64>,
+}
+
+pub async fn fetch_markets(client: &reqwest::Client, ids: &[String], upstream: &Upstream) -> Result<Vec<MarketData>, PriceError> {
+    let mut markets = Vec::with_capacity(ids.len());
+    for batch in ids.chunks(PRICE_BATCH_SIZE) {
+        let url = format!("https://api.coingecko.com/api/v3/coins/markets?vs_currency=usd&ids={}", batch.join(","));
+        let batch: Vec<MarketData> = fetch_with_retry(client, &url, upstream, |body| Ok(serde_json::from_str(body)?)).await?;
+        markets.extend(batch);
+    }
+    Ok(markets)
+}
+
+pub async fn fetch_top_markets(client: &reqwest::Client, count: usize, upstream: &Upstream) -> Result<Vec<MarketData>, PriceError> {
+    let url = format!(
+        "https://api.coingecko.com/api/v3/coins/markets?vs_currency=usd&order=market_cap_desc&per_page={}&page=1",
+        count
+    );
+    fetch_with_retry(client, &url, upstream, |body| Ok(serde_json::from_str(body)?)).await
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrendingCoin {
+    pub name: String,
+    pub symbol: String,
+    pub market_cap_rank: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct TrendingItem {
+    item: TrendingCoin,
+}
+
+#[derive(Deserialize)]
+struct TrendingResponse {
+    #[serde(default)]
+    coins: Vec<Value>,
+}
+
+/// Trending coins in CoinGecko's order, skipping any entry that doesn't
+/// parse rather than failing the whole list.
+pub async fn fetch_trending(client: &reqwest::Client, upstream: &Upstream) -> Result<Vec<TrendingCoin>, PriceError> {
+    let url = "https://api.coingecko.com/api/v3/search/trending";
+    let response: TrendingResponse = fetch_with_retry(client, url, upstream, |body| Ok(serde_json::from_str(body)?)).await?;
+    Ok(response
+        .coins
+        .into_iter()
+        .filter_map(|coin| serde_json::from_value::<TrendingItem>(coin).ok())
+        .map(|coin| coin.item)
+        .collect())
+}
+
+/// Market-wide figures from CoinGecko's `/global` endpoint.
+pub struct GlobalMarket {
+    pub total_market_cap_usd: Option<f64>,
+    /// Market-cap share by lowercase symbol, in percent.
+    pub dominance: HashMap<String, f64>,
+}
+
+pub fn parse_global(body: &str) -> Result<GlobalMarket, PriceError> {
+    let json: Value = serde_json::from_str(body)?;
+    let data = &json["data"];
+    let percentages = data["market_cap_percentage"]
+        .as_object()
+        .ok_or(PriceError::MissingField("market_cap_percentage"))?;
+    // Skip anything that isn't a number rather than failing the whole reply.
+    let dominance = percentages
+        .iter()
+        .filter_map(|(symbol, share)| share.as_f64().map(|share| (symbol.to_lowercase(), share)))
+        .collect();
+    Ok(GlobalMarket {
+        total_market_cap_usd: data["total_market_cap"]["usd"].as_f64(),
+        dominance,
+    })
+}
+
+/// The latest reading of alternative.me's Crypto Fear & Greed Index.
+#[derive(Debug, Clone)]
+pub struct FearGreed {
+    /// 0 (extreme fear) to 100 (extreme greed).
+    pub value: u8,
+    pub classification: String,
+    /// Unix time the reading was published.
+    pub timestamp: i64,
+}
+
+/// Fetches the current index, or `None` when the API has no reading to give.
+pub async fn fetch_fear_greed(client: &reqwest::Client, upstream: &Upstream) -> Result<Option<FearGreed>, PriceError> {
+    fetch_with_retry(client, "https://api.alternative.me/fng/", upstream, parse_fear_greed).await
+}
+
+fn parse_fear_greed(body: &str) -> Result<Option<FearGreed>, PriceError> {
+    let json: Value = serde_json::from_str(body)?;
+    let latest = match json["data"].as_array().and_then(|data| data.first()) {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+    // Every field comes back as a string.
+    let value = latest["value"]
+        .as_str()
+        .and_then(|value| value.parse::<u8>().ok())
+        .ok_or(PriceError::MissingField("value"))?;
+    let classification = latest["value_classification"]
+        .as_str()
+        .ok_or(PriceError::MissingField("value_classification"))?;
+    let timestamp = latest["timestamp"]
+        .as_str()
+        .and_then(|timestamp| timestamp.parse::<i64>().ok())
+        .ok_or(PriceError::MissingField("timestamp"))?;
+    Ok(Some(FearGreed {
+        value: value.min(100),
+        classification: classification.to_string(),
+        timestamp,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const ETH_PRICE_BODY: &str = r#"{"status":"1","message":"OK","result":{"ethbtc":"0.05","ethusd":"3120.55"}}"#;
+
+    /// Longer than any deadline or client timeout used below.
+    const SLOW_RESPONSE: Duration = Duration::from_secs(5);
+
+    /// A policy for tests: quick retries and no breakers, with fresh per-API state.
+    fn upstream(max_retries: u32) -> Upstream {
+        Upstream {
+            retry: RetryPolicy {
+                max_retries,
+                base_delay: Duration::from_millis(1),
+                breaker_threshold: 0,
+                breaker_cooldown: Duration::from_secs(30),
+            },
+            breakers: Arc::new(CircuitBreakers::default()),
+            cooldowns: Arc::new(Cooldowns::default()),
+        }
+    }
+
+    /// A server answering every GET with `response`.
+    async fn mock_server(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).respond_with(response).mount(&server).await;
+        server
+    }
+
+    /// A server answering Etherscan's ETH price query at `/api` with `response`.
+    async fn etherscan_mock(response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api"))
+            .and(query_param("module", "stats"))
+            .and(query_param("action", "ethprice"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    async fn fetch_text(client: &reqwest::Client, url: &str, max_retries: u32) -> Result<String, PriceError> {
+        fetch_with_retry(client, url, &upstream(max_retries), |body| Ok(body.to_string())).await
+    }
+
+    /// A fetch that counts its calls and returns the next of `prices`.
+    fn counting_fetch<'a>(
+        calls: &'a AtomicUsize,
+        prices: &'a [Result<f64, ()>],
+    ) -> impl Fn() -> std::future::Ready<Result<f64, PriceError>> + 'a {
+        move || {
+            let call = calls.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(prices[call].map_err(|()| PriceError::Busy))
+        }
+    }
+
+    fn config(vars: &[(&str, &str)]) -> Config {
+        Config::from_lookup(|key| vars.iter().find(|(name, _)| *name == key).map(|(_, value)| value.to_string())).unwrap()
+    }
+
+    /// A provider that always quotes the same price, counting how often it's asked.
+    struct Fixed(f64, AtomicUsize);
+
+    #[async_trait]
+    impl PriceProvider for Fixed {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        async fn eth_price(&self, _currency: &str) -> Result<f64, PriceError> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            Ok(self.0)
+        }
+    }
+
+    struct Failing;
+
+    #[async_trait]
+    impl PriceProvider for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn eth_price(&self, _currency: &str) -> Result<f64, PriceError> {
+            Err(PriceError::Busy)
+        }
+    }
+
+    #[test]
+    fn missing_price_field_is_reported() {
+        let body = r#"{"status":"1","message":"OK","result":{"ethbtc":"0.05"}}"#;
+        assert!(matches!(parse_eth_price(body, "ethusd"), Err(PriceError::MissingField("ethusd"))));
+    }
+
+    #[test]
+    fn malformed_json_is_a_parse_error() {
+        assert!(matches!(parse_eth_price(r#"{"status":"1","result":"#, "ethusd"), Err(PriceError::Parse(_))));
+        assert!(matches!(parse_eth_price("<html>502 Bad Gateway</html>", "ethusd"), Err(PriceError::Parse(_))));
+    }
+
+    #[test]
+    fn etherscan_rate_limit_envelope_is_busy() {
+        let body = r#"{"status":"0","message":"NOTOK","result":"Max rate limit reached"}"#;
+        assert!(matches!(parse_eth_price(body, "ethusd"), Err(PriceError::Busy)));
+    }
+
+    #[test]
+    fn other_etherscan_errors_carry_their_reason() {
+        let body = r#"{"status":"0","message":"NOTOK","result":"Invalid API Key"}"#;
+        match parse_eth_price(body, "ethusd") {
+            Err(PriceError::Upstream(reason)) => assert_eq!(reason, "Invalid API Key"),
+            other => panic!("expected an upstream error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_eth_price_from_etherscan() {
+        let server = etherscan_mock(ResponseTemplate::new(200).set_body_string(ETH_PRICE_BODY)).await;
+        let url = format!("{}/api", server.uri());
+        let price = fetch_eth_price(&reqwest::Client::new(), &url, "key", &upstream(0)).await.unwrap();
+        assert_eq!(price, 3120.55);
+    }
+
+    #[tokio::test]
+    async fn server_error_fails_the_fetch() {
+        let server = etherscan_mock(ResponseTemplate::new(500)).await;
+        let url = format!("{}/api", server.uri());
+        let result = fetch_eth_price(&reqwest::Client::new(), &url, "key", &upstream(0)).await;
+        assert!(matches!(result, Err(PriceError::Busy)));
+    }
+
+    #[tokio::test]
+    async fn malformed_response_fails_the_fetch() {
+        let server = etherscan_mock(ResponseTemplate::new(200).set_body_string(r#"{"status":"1","result":{"#)).await;
+        let url = format!("{}/api", server.uri());
+        let result = fetch_eth_price(&reqwest::Client::new(), &url, "key", &upstream(0)).await;
+        assert!(matches!(result, Err(PriceError::Parse(_))));
+    }
+
+    #[tokio::test]
+    async fn cached_price_requests_once_within_the_ttl() {
+        let server = etherscan_mock(ResponseTemplate::new(200).set_body_string(ETH_PRICE_BODY)).await;
+        let client = reqwest::Client::new();
+        let url = format!("{}/api", server.uri());
+        let upstream = upstream(0);
+        let cache = Mutex::new(HashMap::new());
+        let fetch = || fetch_eth_price(&client, &url, "key", &upstream);
+        let first = cached_price(&cache, "usd", Duration::from_secs(30), fetch).await.unwrap();
+        let second = cached_price(&cache, "usd", Duration::from_secs(30), fetch).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cached_price_is_refetched_once_stale() {
+        let calls = AtomicUsize::new(0);
+        let fetch = counting_fetch(&calls, &[Ok(3000.0), Ok(3100.0)]);
+        let cache = Mutex::new(HashMap::new());
+        cached_price(&cache, "usd", Duration::ZERO, &fetch).await.unwrap();
+        let (price, _) = cached_price(&cache, "usd", Duration::ZERO, &fetch).await.unwrap();
+        assert_eq!(price, 3100.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn cached_price_is_kept_per_key_and_failures_are_not_cached() {
+        let calls = AtomicUsize::new(0);
+        let fetch = counting_fetch(&calls, &[Err(()), Ok(3000.0), Ok(0.05)]);
+        let cache = Mutex::new(HashMap::new());
+        assert!(cached_price(&cache, "usd", Duration::from_secs(30), &fetch).await.is_err());
+        let (usd, _) = cached_price(&cache, "usd", Duration::from_secs(30), &fetch).await.unwrap();
+        let (btc, _) = cached_price(&cache, "btc", Duration::from_secs(30), &fetch).await.unwrap();
+        assert_eq!((usd, btc), (3000.0, 0.05));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn eth_price_comes_from_coingecko_without_an_etherscan_key() {
+        let config = config(&[("DISCORD_TOKEN", "token")]);
+        assert_eq!(config.etherscan_api_key, None);
+        assert_eq!(eth_price_source(&config, "usd"), EthPriceSource::CoinGecko);
+        assert_eq!(eth_price_source(&config, "eur"), EthPriceSource::CoinGecko);
+    }
+
+    #[test]
+    fn eth_price_comes_from_etherscan_with_a_key() {
+        let config = config(&[("DISCORD_TOKEN", "token"), ("ETHERSCAN_API_KEY", "key")]);
+        assert_eq!(eth_price_source(&config, "usd"), EthPriceSource::Etherscan { api_key: "key" });
+        assert_eq!(eth_price_source(&config, "eur"), EthPriceSource::CoinGecko);
+    }
+
+    #[tokio::test]
+    async fn fallback_uses_the_next_provider_when_one_fails() {
+        let secondary = Fixed(3120.55, AtomicUsize::new(0));
+        let fallback = FallbackProvider { providers: vec![&Failing, &secondary] };
+        assert_eq!(fallback.eth_price("usd").await.unwrap(), 3120.55);
+        assert_eq!(secondary.1.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fallback_stops_at_the_first_price() {
+        let primary = Fixed(3120.55, AtomicUsize::new(0));
+        let secondary = Fixed(3000.0, AtomicUsize::new(0));
+        let fallback = FallbackProvider { providers: vec![&primary, &secondary] };
+        assert_eq!(fallback.eth_price("usd").await.unwrap(), 3120.55);
+        assert_eq!(secondary.1.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn fallback_returns_the_last_error_when_every_provider_fails() {
+        let fallback = FallbackProvider { providers: vec![&Failing, &Failing] };
+        assert!(matches!(fallback.eth_price("usd").await, Err(PriceError::Busy)));
+    }
+
+    #[tokio::test]
+    async fn etherscan_reports_currencies_it_does_not_quote() {
+        let server = mock_server(ResponseTemplate::new(200).set_body_string(ETH_PRICE_BODY)).await;
+        let etherscan = Etherscan {
+            client: Arc::new(reqwest::Client::new()),
+            base_url: format!("{}/api", server.uri()),
+            api_key: "key".to_string(),
+            upstream: upstream(0),
+        };
+        assert!(matches!(etherscan.eth_price("eur").await, Err(PriceError::UnsupportedCurrency(currency)) if currency == "eur"));
+        assert_eq!(server.received_requests().await.unwrap().len(), 0);
+
+        let coingecko = Fixed(2870.10, AtomicUsize::new(0));
+        let fallback = FallbackProvider { providers: vec![&etherscan, &coingecko] };
+        assert_eq!(fallback.eth_price("eur").await.unwrap(), 2870.10);
+    }
+
+    #[tokio::test]
+    async fn client_timeout_fails_a_slow_fetch() {
+        let server = mock_server(ResponseTemplate::new(200).set_delay(SLOW_RESPONSE)).await;
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let why = fetch_text(&client, &server.uri(), 0).await.unwrap_err();
+        assert!(matches!(why, PriceError::Http(_)));
+        assert!(why.is_timeout());
+    }
+
+    #[tokio::test]
+    async fn http_errors_do_not_reveal_the_api_key() {
+        let server = mock_server(ResponseTemplate::new(200).set_delay(SLOW_RESPONSE)).await;
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let url = format!("{}/api?module=stats&action=ethprice&apikey=SECRETKEY", server.uri());
+        let why = fetch_text(&client, &url, 0).await.unwrap_err();
+        assert!(matches!(why, PriceError::Http(_)));
+        assert!(!why.to_string().contains("SECRETKEY"), "{}", why);
+        assert!(!format!("{:?}", why).contains("SECRETKEY"), "{:?}", why);
+    }
+
+    #[tokio::test]
+    async fn retries_past_transient_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_eq!(fetch_text(&reqwest::Client::new(), &server.uri(), 3).await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retries_run_out() {
+        let server = mock_server(ResponseTemplate::new(503)).await;
+        let result = fetch_text(&reqwest::Client::new(), &server.uri(), 2).await;
+        assert!(matches!(result, Err(PriceError::Busy)));
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn deadline_cuts_off_a_server_that_sleeps_past_it() {
+        let server = mock_server(ResponseTemplate::new(200).set_delay(SLOW_RESPONSE)).await;
+        let started = Instant::now();
+        let result = with_deadline(Duration::from_millis(100), reqwest::Client::new().get(server.uri()).send()).await;
+        assert!(matches!(result, Err(PriceError::TimedOut)));
+        assert!(started.elapsed() < SLOW_RESPONSE);
+    }
+
+    #[test]
+    fn deadline_shrinks_to_what_is_left_of_the_retry_budget() {
+        assert_eq!(step_deadline(Instant::now()), REQUEST_DEADLINE);
+        let nearly_spent = Instant::now() - (RETRY_BUDGET - Duration::from_secs(2));
+        assert!(step_deadline(nearly_spent) <= Duration::from_secs(2));
+        assert_eq!(step_deadline(Instant::now() - RETRY_BUDGET), Duration::ZERO);
+    }
+
+    #[test]
+    fn resolve_symbol_normalizes_and_rejects_blank_input() {
+        let symbols = SymbolCache::well_known();
+        assert_eq!(resolve_symbol(&symbols, " ETH "), Some("ethereum".to_string()));
+        assert_eq!(resolve_symbol(&symbols, "  "), None);
+        assert_eq!(resolve_symbol(&symbols, "not-a-coin"), None);
+    }
+}
@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
+use serenity::prelude::*;
+use tokio::sync::{oneshot, Notify};
+use tracing::{error, warn};
+
+use crate::breaker::{CircuitBreakers, Cooldowns};
+use crate::config::Config;
+use crate::health::BotStatus;
+use crate::providers::{FearGreed, MarketData, SymbolCache, TrendingCoin, Upstream};
+use crate::storage::Store;
+
+pub struct ShardManagerContainer;
+
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+pub struct CommandCounter;
+
+impl TypeMapKey for CommandCounter {
+    type Value = HashMap<String, u64>;
+}
+
+/// Reads a JSON state file, falling back to the default value if the file is
+/// missing or corrupt.
+pub async fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(why) => {
+            if why.kind() != std::io::ErrorKind::NotFound {
+                warn!(path = %path.display(), error = %why, "Couldn't read state file, starting empty");
+            }
+            return T::default();
+        }
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|why| {
+        warn!(path = %path.display(), error = %why, "State file is corrupt, starting empty");
+        T::default()
+    })
+}
+
+/// Copies the JSON files earlier versions kept state in into a freshly
+/// created database. Missing files are skipped.
+pub async fn import_legacy_json(storage: &dyn Store, config: &Config) -> rusqlite::Result<()> {
+    let counts: HashMap<String, u64> = load_json(&config.command_counter_path).await;
+    storage.save_command_counts(&counts)?;
+
+    let prefixes: HashMap<GuildId, String> = load_json(&config.guild_prefixes_path).await;
+    for (guild_id, prefix) in &prefixes {
+        storage.set_guild_prefix(guild_id.0, prefix)?;
+    }
+
+    let settings: HashMap<GuildId, GuildSettings> = load_json(&config.guild_settings_path).await;
+    for (guild_id, settings) in &settings {
+        storage.set_guild_embeds(guild_id.0, settings.use_embeds)?;
+    }
+
+    let portfolios: HashMap<UserId, HashMap<String, f64>> = load_json(&config.portfolios_path).await;
+    for (user_id, holdings) in &portfolios {
+        for (symbol, quantity) in holdings {
+            storage.set_holding(user_id.0, symbol, *quantity)?;
+        }
+    }
+
+    if config.allowed_channels_path.exists() {
+        let allowed: HashSet<ChannelId> = load_json(&config.allowed_channels_path).await;
+        storage.set_allowed_channels(&allowed.iter().map(|channel_id| channel_id.0).collect())?;
+    }
+    Ok(())
+}
+
+pub async fn flush_command_counter(data: &RwLock<TypeMap>) {
+    let (counter, storage) = {
+        let data = data.read().await;
+        let counter = data.get::<CommandCounter>().cloned().unwrap_or_default();
+        (counter, data.get::<StorageContainer>().cloned().expect("Expected StorageContainer in TypeMap."))
+    };
+    if let Err(why) = storage.save_command_counts(&counter) {
+        error!(error = %why, "Couldn't save command counts");
+    }
+}
+
+/// Per-guild presentation preferences.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub use_embeds: bool,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        GuildSettings { use_embeds: true }
+    }
+}
+
+pub struct GuildSettingsContainer;
+
+impl TypeMapKey for GuildSettingsContainer {
+    type Value = HashMap<GuildId, GuildSettings>;
+}
+
+/// Each user's holdings as quantity by lowercase symbol.
+pub struct Portfolios;
+
+impl TypeMapKey for Portfolios {
+    type Value = HashMap<UserId, HashMap<String, f64>>;
+}
+
+/// Quote currencies chosen with `setcurrency`, by user.
+pub struct UserPreferences;
+
+impl TypeMapKey for UserPreferences {
+    type Value = HashMap<UserId, String>;
+}
+
+/// The currency `user_id` wants prices in: their own choice, else `DEFAULT_CURRENCY`.
+pub async fn preferred_currency(ctx: &Context, user_id: UserId) -> String {
+    let data = ctx.data.read().await;
+    let preferences = data.get::<UserPreferences>().expect("Expected UserPreferences in TypeMap.");
+    match preferences.get(&user_id) {
+        Some(currency) => currency.clone(),
+        None => data.get::<ConfigContainer>().expect("Expected ConfigContainer in TypeMap.").default_currency.clone(),
+    }
+}
+
+/// Custom command prefixes set with `setprefix`, by guild.
+pub struct GuildPrefixes;
+
+impl TypeMapKey for GuildPrefixes {
+    type Value = HashMap<GuildId, String>;
+}
+
+/// How often a command has failed, with its most recent errors.
+#[derive(Default)]
+pub struct CommandFailures {
+    pub count: u64,
+    pub recent: VecDeque<String>,
+}
+
+/// Errors kept per command in `CommandFailureCounter`.
+pub const RECENT_FAILURES: usize = 5;
+
+pub struct CommandFailureCounter;
+
+impl TypeMapKey for CommandFailureCounter {
+    type Value = HashMap<String, CommandFailures>;
+}
+
+/// When each in-flight command started, so `after` can report its latency.
+pub struct CommandTimings;
+
+impl TypeMapKey for CommandTimings {
+    type Value = HashMap<MessageId, Instant>;
+}
+
+/// Stops the delayed typing indicator of each in-flight slow command when
+/// its sender is dropped.
+pub struct TypingIndicators;
+
+impl TypeMapKey for TypingIndicators {
+    type Value = HashMap<MessageId, oneshot::Sender<()>>;
+}
+
+pub struct LiveTicker {
+    pub owner: UserId,
+    pub stop: Arc<Notify>,
+}
+
+pub struct LiveTickers;
+
+impl TypeMapKey for LiveTickers {
+    type Value = HashMap<MessageId, LiveTicker>;
+}
+
+pub struct BotStatusContainer;
+
+impl TypeMapKey for BotStatusContainer {
+    type Value = Arc<BotStatus>;
+}
+
+/// When the process started, for the uptime `about` reports.
+pub struct StartTime;
+
+impl TypeMapKey for StartTime {
+    type Value = Instant;
+}
+
+pub struct AllowedChannels;
+
+impl TypeMapKey for AllowedChannels {
+    type Value = HashSet<ChannelId>;
+}
+
+pub struct BotOwners;
+
+impl TypeMapKey for BotOwners {
+    type Value = HashSet<UserId>;
+}
+
+pub struct ConfigContainer;
+
+impl TypeMapKey for ConfigContainer {
+    type Value = Arc<Config>;
+}
+
+pub async fn config(ctx: &Context) -> Arc<Config> {
+    let data = ctx.data.read().await;
+    data.get::<ConfigContainer>().cloned().expect("Expected ConfigContainer in TypeMap.")
+}
+
+/// Recently fetched ETH prices keyed by quote currency, with the time they were fetched.
+pub struct PriceCache;
+
+impl TypeMapKey for PriceCache {
+    type Value = Arc<Mutex<HashMap<String, (f64, Instant)>>>;
+}
+
+pub struct TrendingCache;
+
+impl TypeMapKey for TrendingCache {
+    type Value = Arc<Mutex<Option<(Vec<TrendingCoin>, Instant)>>>;
+}
+
+pub struct TopMarketsCache;
+
+impl TypeMapKey for TopMarketsCache {
+    type Value = Arc<Mutex<Option<(Vec<MarketData>, Instant)>>>;
+}
+
+pub struct FearGreedCache;
+
+impl TypeMapKey for FearGreedCache {
+    type Value = Arc<Mutex<Option<(FearGreed, Instant)>>>;
+}
+
+pub struct HttpClientContainer;
+
+impl TypeMapKey for HttpClientContainer {
+    type Value = Arc<reqwest::Client>;
+}
+
+/// Fetches the shared HTTP client, releasing the data lock before any request is made.
+pub async fn http_client(ctx: &Context) -> Arc<reqwest::Client> {
+    let data = ctx.data.read().await;
+    data.get::<HttpClientContainer>().cloned().expect("Expected HttpClientContainer in TypeMap.")
+}
+
+/// Circuit breakers for each upstream API, shared by commands and background tasks.
+pub struct CircuitBreakerContainer;
+
+impl TypeMapKey for CircuitBreakerContainer {
+    type Value = Arc<CircuitBreakers>;
+}
+
+/// Rate-limit cooldowns for each upstream API, so requests short-circuit
+/// instead of piling on more 429s.
+pub struct ProviderCooldown;
+
+impl TypeMapKey for ProviderCooldown {
+    type Value = Arc<Cooldowns>;
+}
+
+/// What an upstream request needs besides the client: the retry policy and the shared per-API state.
+pub async fn upstream(ctx: &Context) -> Upstream {
+    let data = ctx.data.read().await;
+    Upstream {
+        retry: data.get::<ConfigContainer>().expect("Expected ConfigContainer in TypeMap.").retry,
+        breakers: data.get::<CircuitBreakerContainer>().cloned().expect("Expected CircuitBreakerContainer in TypeMap."),
+        cooldowns: data.get::<ProviderCooldown>().cloned().expect("Expected ProviderCooldown in TypeMap."),
+    }
+}
+
+pub struct SymbolCacheContainer;
+
+impl TypeMapKey for SymbolCacheContainer {
+    type Value = Arc<SymbolCache>;
+}
+
+pub struct StorageContainer;
+
+impl TypeMapKey for StorageContainer {
+    type Value = Arc<dyn Store>;
+}
+
+pub async fn storage(ctx: &Context) -> Arc<dyn Store> {
+    let data = ctx.data.read().await;
+    data.get::<StorageContainer>().cloned().expect("Expected StorageContainer in TypeMap.")
+}
+
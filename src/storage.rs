@@ -0,0 +1,508 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::alerts::{Alert, AlertKind, Direction};
+
+/// Schema changes in the order they were introduced. `PRAGMA user_version`
+/// records how many have been applied, so only new ones run at startup.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE holdings (
+        user_id  INTEGER NOT NULL,
+        symbol   TEXT NOT NULL,
+        quantity REAL NOT NULL,
+        PRIMARY KEY (user_id, symbol)
+    );
+    CREATE TABLE guild_prefixes (
+        guild_id INTEGER PRIMARY KEY,
+        prefix   TEXT NOT NULL
+    );
+    CREATE TABLE guild_settings (
+        guild_id   INTEGER PRIMARY KEY,
+        use_embeds INTEGER NOT NULL
+    );
+    CREATE TABLE allowed_channels (
+        channel_id INTEGER PRIMARY KEY
+    );
+    CREATE TABLE command_counts (
+        command TEXT PRIMARY KEY,
+        count   INTEGER NOT NULL
+    );
+    CREATE TABLE flags (
+        name TEXT PRIMARY KEY
+    );",
+    "CREATE TABLE user_currencies (
+        user_id  INTEGER PRIMARY KEY,
+        currency TEXT NOT NULL
+    );",
+    "CREATE TABLE alerts (
+        id        INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id   INTEGER NOT NULL,
+        symbol    TEXT NOT NULL,
+        coin_id   TEXT NOT NULL,
+        direction TEXT NOT NULL,
+        threshold REAL NOT NULL
+    );",
+    "ALTER TABLE alerts ADD COLUMN kind TEXT NOT NULL DEFAULT 'alert';",
+];
+
+/// Set once the allow-list has been edited, after which it overrides `ALLOWED_CHANNEL_IDS`.
+const ALLOWED_CHANNELS_SAVED: &str = "allowed_channels_saved";
+
+/// Set once the alerts from the old standalone alerts database have been copied in.
+const LEGACY_ALERTS_IMPORTED: &str = "legacy_alerts_imported";
+
+const ALERT_COLUMNS: &str = "id, user_id, symbol, coin_id, direction, threshold";
+
+/// Everything the bot persists, so commands and tests can run against any
+/// backend. `Storage` is the SQLite one.
+pub trait Store: Send + Sync {
+    /// Every user's holdings as quantity by lowercase symbol.
+    fn holdings(&self) -> rusqlite::Result<HashMap<u64, HashMap<String, f64>>>;
+    fn set_holding(&self, user_id: u64, symbol: &str, quantity: f64) -> rusqlite::Result<()>;
+    /// Removes `user_id`'s holding of `symbol`, returning whether it existed.
+    fn remove_holding(&self, user_id: u64, symbol: &str) -> rusqlite::Result<bool>;
+
+    fn guild_prefixes(&self) -> rusqlite::Result<HashMap<u64, String>>;
+    fn set_guild_prefix(&self, guild_id: u64, prefix: &str) -> rusqlite::Result<()>;
+    /// Whether each guild that has chosen wants price replies as embeds.
+    fn guild_embed_settings(&self) -> rusqlite::Result<HashMap<u64, bool>>;
+    fn set_guild_embeds(&self, guild_id: u64, use_embeds: bool) -> rusqlite::Result<()>;
+
+    /// The saved allow-list, or `None` if it has never been edited.
+    fn allowed_channels(&self) -> rusqlite::Result<Option<HashSet<u64>>>;
+    /// Replaces the saved allow-list.
+    fn set_allowed_channels(&self, channels: &HashSet<u64>) -> rusqlite::Result<()>;
+
+    /// Each user's preferred quote currency.
+    fn user_currencies(&self) -> rusqlite::Result<HashMap<u64, String>>;
+    fn set_user_currency(&self, user_id: u64, currency: &str) -> rusqlite::Result<()>;
+
+    fn command_counts(&self) -> rusqlite::Result<HashMap<String, u64>>;
+    fn save_command_counts(&self, counts: &HashMap<String, u64>) -> rusqlite::Result<()>;
+
+    fn add_alert(&self, user_id: u64, symbol: &str, coin_id: &str, direction: Direction, threshold: f64) -> rusqlite::Result<i64>;
+    /// Replaces any watch `user_id` already has on `coin_id` with a new one,
+    /// leaving their alerts alone.
+    fn upsert_watch(&self, user_id: u64, symbol: &str, coin_id: &str, direction: Direction, threshold: f64) -> rusqlite::Result<i64>;
+    /// Every pending alert and watch, oldest first.
+    fn alerts(&self) -> rusqlite::Result<Vec<Alert>>;
+    /// `user_id`'s alerts or watches, oldest first.
+    fn user_alerts(&self, user_id: u64, kind: AlertKind) -> rusqlite::Result<Vec<Alert>>;
+    /// Removes one of `user_id`'s alerts, returning whether it existed.
+    fn remove_alert(&self, user_id: u64, id: i64) -> rusqlite::Result<bool>;
+    /// Removes `user_id`'s watch on `coin_id`, returning whether it existed.
+    fn remove_watch(&self, user_id: u64, coin_id: &str) -> rusqlite::Result<bool>;
+    fn delete_alert(&self, id: i64) -> rusqlite::Result<()>;
+    /// Removes every alert and watch, returning how many there were.
+    fn clear_alerts(&self) -> rusqlite::Result<usize>;
+}
+
+/// Bot state persisted in SQLite: holdings, per-guild settings, alerts and command counts.
+pub struct Storage {
+    conn: Mutex<Connection>,
+    created: bool,
+}
+
+impl Storage {
+    /// Opens or creates the database at `path`, bringing its schema up to
+    /// date. A path of `:memory:` gives a private in-memory database.
+    pub fn open(path: &Path) -> rusqlite::Result<Storage> {
+        Storage::migrate(Connection::open(path)?)
+    }
+
+    fn migrate(mut conn: Connection) -> rusqlite::Result<Storage> {
+        let applied: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        for (version, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", version as i64 + 1)?;
+            tx.commit()?;
+        }
+        Ok(Storage {
+            conn: Mutex::new(conn),
+            created: applied == 0,
+        })
+    }
+
+    /// Whether this database was created by this process rather than reopened.
+    pub fn is_new(&self) -> bool {
+        self.created
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().expect("storage poisoned")
+    }
+
+    /// Copies the alerts from the standalone database earlier versions kept
+    /// them in, once. Returns how many were copied.
+    pub fn import_legacy_alerts(&self, path: &Path) -> rusqlite::Result<usize> {
+        let mut conn = self.conn();
+        let imported = conn
+            .query_row("SELECT 1 FROM flags WHERE name = ?1", params![LEGACY_ALERTS_IMPORTED], |_| Ok(()))
+            .optional()?;
+        if imported.is_some() {
+            return Ok(0);
+        }
+        conn.execute("ATTACH DATABASE ?1 AS legacy", params![&*path.to_string_lossy()])?;
+        let copied = copy_legacy_alerts(&mut conn);
+        conn.execute("DETACH DATABASE legacy", [])?;
+        copied
+    }
+
+    fn query_alerts(&self, condition: &str, params: &[&dyn rusqlite::ToSql]) -> rusqlite::Result<Vec<Alert>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM alerts {} ORDER BY id", ALERT_COLUMNS, condition))?;
+        let rows = stmt.query_map(params, |row| {
+            let direction: String = row.get(4)?;
+            Ok(Alert {
+                id: row.get(0)?,
+                user_id: row.get::<_, i64>(1)? as u64,
+                symbol: row.get(2)?,
+                coin_id: row.get(3)?,
+                direction: Direction::parse(&direction).unwrap_or(Direction::Above),
+                threshold: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+/// Copies every alert from the attached `legacy` database, recording that
+/// the import is done in the same transaction.
+fn copy_legacy_alerts(conn: &mut Connection) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    let copied = tx.execute(
+        &format!("INSERT INTO alerts ({0}) SELECT {0} FROM legacy.alerts ORDER BY id", ALERT_COLUMNS),
+        [],
+    )?;
+    tx.execute("INSERT INTO flags (name) VALUES (?1)", params![LEGACY_ALERTS_IMPORTED])?;
+    tx.commit()?;
+    Ok(copied)
+}
+
+impl Store for Storage {
+    fn holdings(&self) -> rusqlite::Result<HashMap<u64, HashMap<String, f64>>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT user_id, symbol, quantity FROM holdings")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)? as u64, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+        })?;
+        let mut holdings: HashMap<u64, HashMap<String, f64>> = HashMap::new();
+        for row in rows {
+            let (user_id, symbol, quantity) = row?;
+            holdings.entry(user_id).or_default().insert(symbol, quantity);
+        }
+        Ok(holdings)
+    }
+
+    fn set_holding(&self, user_id: u64, symbol: &str, quantity: f64) -> rusqlite::Result<()> {
+        self.conn().execute(
+            "INSERT INTO holdings (user_id, symbol, quantity) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id, symbol) DO UPDATE SET quantity = excluded.quantity",
+            params![user_id as i64, symbol, quantity],
+        )?;
+        Ok(())
+    }
+
+    fn remove_holding(&self, user_id: u64, symbol: &str) -> rusqlite::Result<bool> {
+        let removed = self.conn().execute(
+            "DELETE FROM holdings WHERE user_id = ?1 AND symbol = ?2",
+            params![user_id as i64, symbol],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn guild_prefixes(&self) -> rusqlite::Result<HashMap<u64, String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT guild_id, prefix FROM guild_prefixes")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    fn set_guild_prefix(&self, guild_id: u64, prefix: &str) -> rusqlite::Result<()> {
+        self.conn().execute(
+            "INSERT INTO guild_prefixes (guild_id, prefix) VALUES (?1, ?2)
+             ON CONFLICT (guild_id) DO UPDATE SET prefix = excluded.prefix",
+            params![guild_id as i64, prefix],
+        )?;
+        Ok(())
+    }
+
+    fn guild_embed_settings(&self) -> rusqlite::Result<HashMap<u64, bool>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT guild_id, use_embeds FROM guild_settings")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    fn set_guild_embeds(&self, guild_id: u64, use_embeds: bool) -> rusqlite::Result<()> {
+        self.conn().execute(
+            "INSERT INTO guild_settings (guild_id, use_embeds) VALUES (?1, ?2)
+             ON CONFLICT (guild_id) DO UPDATE SET use_embeds = excluded.use_embeds",
+            params![guild_id as i64, use_embeds],
+        )?;
+        Ok(())
+    }
+
+    fn allowed_channels(&self) -> rusqlite::Result<Option<HashSet<u64>>> {
+        let conn = self.conn();
+        let saved = conn
+            .query_row("SELECT 1 FROM flags WHERE name = ?1", params![ALLOWED_CHANNELS_SAVED], |_| Ok(()))
+            .optional()?;
+        if saved.is_none() {
+            return Ok(None);
+        }
+        let mut stmt = conn.prepare("SELECT channel_id FROM allowed_channels")?;
+        let rows = stmt.query_map([], |row| Ok(row.get::<_, i64>(0)? as u64))?;
+        rows.collect::<rusqlite::Result<_>>().map(Some)
+    }
+
+    fn set_allowed_channels(&self, channels: &HashSet<u64>) -> rusqlite::Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM allowed_channels", [])?;
+        for channel_id in channels {
+            tx.execute("INSERT INTO allowed_channels (channel_id) VALUES (?1)", params![*channel_id as i64])?;
+        }
+        tx.execute("INSERT OR IGNORE INTO flags (name) VALUES (?1)", params![ALLOWED_CHANNELS_SAVED])?;
+        tx.commit()
+    }
+
+    fn user_currencies(&self) -> rusqlite::Result<HashMap<u64, String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT user_id, currency FROM user_currencies")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    fn set_user_currency(&self, user_id: u64, currency: &str) -> rusqlite::Result<()> {
+        self.conn().execute(
+            "INSERT INTO user_currencies (user_id, currency) VALUES (?1, ?2)
+             ON CONFLICT (user_id) DO UPDATE SET currency = excluded.currency",
+            params![user_id as i64, currency],
+        )?;
+        Ok(())
+    }
+
+    fn command_counts(&self) -> rusqlite::Result<HashMap<String, u64>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT command, count FROM command_counts")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as u64)))?;
+        rows.collect()
+    }
+
+    fn save_command_counts(&self, counts: &HashMap<String, u64>) -> rusqlite::Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        for (command, count) in counts {
+            tx.execute(
+                "INSERT INTO command_counts (command, count) VALUES (?1, ?2)
+                 ON CONFLICT (command) DO UPDATE SET count = excluded.count",
+                params![command, *count as i64],
+            )?;
+        }
+        tx.commit()
+    }
+
+    fn add_alert(&self, user_id: u64, symbol: &str, coin_id: &str, direction: Direction, threshold: f64) -> rusqlite::Result<i64> {
+        let conn = self.conn();
+        conn.execute(
+            "INSERT INTO alerts (user_id, symbol, coin_id, direction, threshold, kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![user_id as i64, symbol, coin_id, direction.as_str(), threshold, AlertKind::Alert.as_str()],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    fn upsert_watch(&self, user_id: u64, symbol: &str, coin_id: &str, direction: Direction, threshold: f64) -> rusqlite::Result<i64> {
+        let mut conn = self.conn();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM alerts WHERE user_id = ?1 AND coin_id = ?2 AND kind = ?3",
+            params![user_id as i64, coin_id, AlertKind::Watch.as_str()],
+        )?;
+        tx.execute(
+            "INSERT INTO alerts (user_id, symbol, coin_id, direction, threshold, kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![user_id as i64, symbol, coin_id, direction.as_str(), threshold, AlertKind::Watch.as_str()],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
+        Ok(id)
+    }
+
+    fn alerts(&self) -> rusqlite::Result<Vec<Alert>> {
+        self.query_alerts("", params![])
+    }
+
+    fn user_alerts(&self, user_id: u64, kind: AlertKind) -> rusqlite::Result<Vec<Alert>> {
+        self.query_alerts("WHERE user_id = ?1 AND kind = ?2", params![user_id as i64, kind.as_str()])
+    }
+
+    fn remove_alert(&self, user_id: u64, id: i64) -> rusqlite::Result<bool> {
+        let removed = self.conn().execute("DELETE FROM alerts WHERE id = ?1 AND user_id = ?2", params![id, user_id as i64])?;
+        Ok(removed > 0)
+    }
+
+    fn remove_watch(&self, user_id: u64, coin_id: &str) -> rusqlite::Result<bool> {
+        let removed = self.conn().execute(
+            "DELETE FROM alerts WHERE user_id = ?1 AND coin_id = ?2 AND kind = ?3",
+            params![user_id as i64, coin_id, AlertKind::Watch.as_str()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    fn delete_alert(&self, id: i64) -> rusqlite::Result<()> {
+        self.conn().execute("DELETE FROM alerts WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    fn clear_alerts(&self) -> rusqlite::Result<usize> {
+        self.conn().execute("DELETE FROM alerts", [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory() -> Storage {
+        Storage::open(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn new_database_has_every_migration_applied() {
+        let storage = memory();
+        assert!(storage.is_new());
+        let version: i64 = storage.conn().query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn holdings_are_set_replaced_and_removed() {
+        let storage = memory();
+        storage.set_holding(1, "eth", 1.5).unwrap();
+        storage.set_holding(1, "eth", 2.0).unwrap();
+        storage.set_holding(2, "btc", 0.1).unwrap();
+        let holdings = storage.holdings().unwrap();
+        assert_eq!(holdings[&1]["eth"], 2.0);
+        assert_eq!(holdings[&2]["btc"], 0.1);
+
+        assert!(storage.remove_holding(1, "eth").unwrap());
+        assert!(!storage.remove_holding(1, "eth").unwrap());
+        assert!(!storage.holdings().unwrap().contains_key(&1));
+    }
+
+    #[test]
+    fn guild_settings_are_kept_per_guild() {
+        let storage = memory();
+        storage.set_guild_prefix(1, "?").unwrap();
+        storage.set_guild_prefix(1, "$").unwrap();
+        storage.set_guild_embeds(2, false).unwrap();
+        assert_eq!(storage.guild_prefixes().unwrap(), HashMap::from([(1, "$".to_string())]));
+        assert_eq!(storage.guild_embed_settings().unwrap(), HashMap::from([(2, false)]));
+    }
+
+    #[test]
+    fn allowed_channels_are_unset_until_saved() {
+        let storage = memory();
+        assert_eq!(storage.allowed_channels().unwrap(), None);
+
+        storage.set_allowed_channels(&HashSet::from([10, 20])).unwrap();
+        storage.set_allowed_channels(&HashSet::from([20])).unwrap();
+        assert_eq!(storage.allowed_channels().unwrap(), Some(HashSet::from([20])));
+
+        storage.set_allowed_channels(&HashSet::new()).unwrap();
+        assert_eq!(storage.allowed_channels().unwrap(), Some(HashSet::new()));
+    }
+
+    #[test]
+    fn user_currencies_and_command_counts_are_overwritten() {
+        let storage = memory();
+        storage.set_user_currency(1, "eur").unwrap();
+        storage.set_user_currency(1, "gbp").unwrap();
+        assert_eq!(storage.user_currencies().unwrap(), HashMap::from([(1, "gbp".to_string())]));
+
+        storage.save_command_counts(&HashMap::from([("price".to_string(), 3)])).unwrap();
+        storage.save_command_counts(&HashMap::from([("price".to_string(), 5), ("gas".to_string(), 1)])).unwrap();
+        let counts = storage.command_counts().unwrap();
+        assert_eq!((counts["price"], counts["gas"]), (5, 1));
+    }
+
+    #[test]
+    fn alerts_are_added_listed_and_removed() {
+        let storage = memory();
+        let first = storage.add_alert(1, "eth", "ethereum", Direction::Above, 4000.0).unwrap();
+        let second = storage.add_alert(1, "btc", "bitcoin", Direction::Below, 20000.0).unwrap();
+        storage.add_alert(2, "eth", "ethereum", Direction::Below, 1000.0).unwrap();
+        assert_eq!(storage.alerts().unwrap().len(), 3);
+
+        let mine = storage.user_alerts(1, AlertKind::Alert).unwrap();
+        assert_eq!(mine.iter().map(|alert| alert.id).collect::<Vec<_>>(), vec![first, second]);
+        assert_eq!(mine[1].direction, Direction::Below);
+        assert_eq!(mine[1].threshold, 20000.0);
+
+        assert!(!storage.remove_alert(2, first).unwrap());
+        assert!(storage.remove_alert(1, first).unwrap());
+        storage.delete_alert(second).unwrap();
+        assert!(storage.user_alerts(1, AlertKind::Alert).unwrap().is_empty());
+        assert_eq!(storage.clear_alerts().unwrap(), 1);
+        assert!(storage.alerts().unwrap().is_empty());
+    }
+
+    #[test]
+    fn upserted_watch_replaces_the_one_on_the_same_coin() {
+        let storage = memory();
+        storage.upsert_watch(1, "eth", "ethereum", Direction::Above, 4000.0).unwrap();
+        let id = storage.upsert_watch(1, "eth", "ethereum", Direction::Below, 1500.0).unwrap();
+        let watches = storage.user_alerts(1, AlertKind::Watch).unwrap();
+        assert_eq!(watches.len(), 1);
+        assert_eq!((watches[0].id, watches[0].threshold), (id, 1500.0));
+        assert!(storage.remove_watch(1, "ethereum").unwrap());
+        assert!(!storage.remove_watch(1, "ethereum").unwrap());
+    }
+
+    #[test]
+    fn watches_leave_alerts_on_the_same_coin_alone() {
+        let storage = memory();
+        let alert_id = storage.add_alert(1, "eth", "ethereum", Direction::Above, 5000.0).unwrap();
+        storage.upsert_watch(1, "eth", "ethereum", Direction::Above, 4000.0).unwrap();
+        storage.upsert_watch(1, "eth", "ethereum", Direction::Below, 1500.0).unwrap();
+        assert_eq!(storage.alerts().unwrap().len(), 2);
+
+        assert!(storage.remove_watch(1, "ethereum").unwrap());
+        let alerts = storage.user_alerts(1, AlertKind::Alert).unwrap();
+        assert_eq!(alerts.iter().map(|alert| alert.id).collect::<Vec<_>>(), vec![alert_id]);
+        assert!(storage.user_alerts(1, AlertKind::Watch).unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_alerts_are_imported_once() {
+        let path = std::env::temp_dir().join(format!("rusty-crypto-legacy-alerts-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let legacy = Connection::open(&path).unwrap();
+        legacy
+            .execute_batch(
+                "CREATE TABLE alerts (
+                    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id   INTEGER NOT NULL,
+                    symbol    TEXT NOT NULL,
+                    coin_id   TEXT NOT NULL,
+                    direction TEXT NOT NULL,
+                    threshold REAL NOT NULL
+                );
+                INSERT INTO alerts (id, user_id, symbol, coin_id, direction, threshold)
+                VALUES (7, 1, 'eth', 'ethereum', 'above', 4000.0);",
+            )
+            .unwrap();
+        drop(legacy);
+
+        let storage = memory();
+        assert_eq!(storage.import_legacy_alerts(&path).unwrap(), 1);
+        assert_eq!(storage.import_legacy_alerts(&path).unwrap(), 0);
+        let alerts = storage.alerts().unwrap();
+        assert_eq!((alerts.len(), alerts[0].id, alerts[0].direction), (1, 7, Direction::Above));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use serenity::model::Timestamp;
+use tracing::{error, info, warn};
+
+use crate::commands::market::change_colour;
+use crate::config::{Config, DailyTime};
+use crate::format::format_price;
+use crate::providers::{fetch_markets, resolve_symbol, MarketData, SymbolCache, Upstream};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Discord allows at most this many fields in one embed.
+const MAX_EMBED_FIELDS: usize = 25;
+
+/// How long from `now` until `at` next comes round, a full day if it is `now`.
+fn until_next(at: DailyTime, now: SystemTime) -> Duration {
+    let since_midnight = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % SECS_PER_DAY;
+    match (at.seconds_into_day() + SECS_PER_DAY - since_midnight) % SECS_PER_DAY {
+        0 => Duration::from_secs(SECS_PER_DAY),
+        secs => Duration::from_secs(secs),
+    }
+}
+
+fn summary_embed<'a>(e: &'a mut CreateEmbed, markets: &[MarketData]) -> &'a mut CreateEmbed {
+    let eth_change = markets
+        .iter()
+        .find(|market| market.id == "ethereum")
+        .and_then(|market| market.price_change_percentage_24h)
+        .filter(|change| change.is_finite());
+    e.title("Daily market summary").colour(change_colour(eth_change)).timestamp(Timestamp::now());
+    for market in markets.iter().take(MAX_EMBED_FIELDS) {
+        let price = market.current_price.map_or("-".to_string(), |price| format_price(price, "usd"));
+        let change = market
+            .price_change_percentage_24h
+            .filter(|change| change.is_finite())
+            .map_or(String::new(), |change| format!(" ({:+.2}%)", change));
+        e.field(format!("{} ({})", market.name, market.symbol.to_uppercase()), format!("{}{}", price, change), true);
+    }
+    e
+}
+
+/// Posts ETH, BTC and `SUMMARY_TOKENS` to `SUMMARY_CHANNEL_ID` every day at
+/// `SUMMARY_TIME_UTC`. Returns at once if no channel is configured. Failed
+/// posts, such as to a deleted channel or one the bot can't write in, are
+/// logged and retried the next day.
+pub async fn post_daily_summaries(
+    http: Arc<Http>,
+    client: Arc<reqwest::Client>,
+    upstream: Upstream,
+    symbols: Arc<SymbolCache>,
+    config: Arc<Config>,
+) {
+    let channel_id = match config.summary_channel {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return,
+    };
+    let mut ids = vec!["ethereum".to_string(), "bitcoin".to_string()];
+    for symbol in &config.summary_tokens {
+        match resolve_symbol(&symbols, symbol) {
+            Some(coin_id) if !ids.contains(&coin_id) => ids.push(coin_id),
+            Some(_) => {},
+            None => warn!(symbol = %symbol, "Unknown token in SUMMARY_TOKENS, leaving it out of the daily summary"),
+        }
+    }
+    info!(channel_id = %channel_id, at = ?config.summary_time, "Daily summary scheduled");
+
+    loop {
+        tokio::time::sleep(until_next(config.summary_time, SystemTime::now())).await;
+
+        let mut markets = match fetch_markets(&client, &ids, &upstream).await {
+            Ok(markets) => markets,
+            Err(why) => {
+                warn!(error = %why, "Couldn't fetch prices for the daily summary");
+                continue;
+            }
+        };
+        // CoinGecko orders by market cap; keep ETH and BTC first, then the configured order.
+        markets.sort_by_key(|market| ids.iter().position(|id| *id == market.id).unwrap_or(usize::MAX));
+
+        if let Err(why) = channel_id.send_message(&http, |m| m.embed(|e| summary_embed(e, &markets))).await {
+            error!(channel_id = %channel_id, error = %why, "Couldn't post the daily summary");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `hour:minute:second` UTC on an arbitrary day.
+    fn time_of_day(hour: u64, minute: u64, second: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(19_000 * SECS_PER_DAY + hour * 3600 + minute * 60 + second)
+    }
+
+    #[test]
+    fn waits_until_later_the_same_day() {
+        let at = DailyTime { hour: 9, minute: 30 };
+        assert_eq!(until_next(at, time_of_day(8, 0, 0)), Duration::from_secs(90 * 60));
+    }
+
+    #[test]
+    fn waits_until_tomorrow_once_the_time_has_passed() {
+        let at = DailyTime { hour: 9, minute: 30 };
+        assert_eq!(until_next(at, time_of_day(10, 0, 0)), Duration::from_secs(SECS_PER_DAY - 30 * 60));
+    }
+
+    #[test]
+    fn waits_a_full_day_exactly_at_the_time() {
+        let at = DailyTime { hour: 9, minute: 30 };
+        assert_eq!(until_next(at, time_of_day(9, 30, 0)), Duration::from_secs(SECS_PER_DAY));
+    }
+
+    #[test]
+    fn wraps_round_midnight() {
+        assert_eq!(until_next(DailyTime { hour: 0, minute: 0 }, time_of_day(23, 59, 30)), Duration::from_secs(30));
+        assert_eq!(until_next(DailyTime { hour: 0, minute: 30 }, time_of_day(23, 0, 0)), Duration::from_secs(90 * 60));
+    }
+}